@@ -0,0 +1,119 @@
+//! Streams replies through many sessions at once using a stand-in for the Claude CLI.
+//!
+//!     cargo bench --bench session_throughput -- [--sessions 20] [--messages 500] [--cli <path>]
+//!
+//! Without `--cli`, a shell script printing `--messages` stream-json replies is used.
+
+use forge_app_lib::core::claude::{ClaudeManager, Message};
+use forge_app_lib::core::mcp::McpRegistry;
+use forge_app_lib::core::projects::ProjectRegistry;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+const FAKE_CLI: &str = r#"#!/bin/sh
+n=${FORGE_BENCH_MESSAGES:-500}
+echo '{"type":"system","subtype":"init","session_id":"bench-'$$'"}'
+i=0
+while [ $i -lt $n ]; do
+  echo '{"type":"assistant","message":{"id":"msg_'$i'","type":"message","role":"assistant","model":"bench","content":[{"type":"text","text":"chunk '$i'"}]}}'
+  i=$((i+1))
+done
+echo '{"type":"result","subtype":"success","is_error":false,"result":"done","session_id":"bench-'$$'","num_turns":1}'
+"#;
+
+struct Args {
+    sessions: usize,
+    messages: usize,
+    cli: Option<PathBuf>,
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        sessions: 20,
+        messages: 500,
+        cli: None,
+    };
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--sessions" => args.sessions = iter.next().and_then(|v| v.parse().ok()).expect("--sessions <n>"),
+            "--messages" => args.messages = iter.next().and_then(|v| v.parse().ok()).expect("--messages <n>"),
+            "--cli" => args.cli = Some(iter.next().expect("--cli <path>").into()),
+            // Flags cargo passes to every bench target
+            _ => {}
+        }
+    }
+    args
+}
+
+fn write_fake_cli() -> PathBuf {
+    let path = std::env::temp_dir().join(format!("forge-bench-cli-{}.sh", std::process::id()));
+    std::fs::write(&path, FAKE_CLI).expect("failed to write fake CLI");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).expect("failed to make fake CLI executable");
+    }
+    path
+}
+
+/// Send one prompt and wait for its completion message, returning how many messages arrived
+async fn run_session(manager: Arc<ClaudeManager>) -> (usize, Duration) {
+    let session_id = manager.create_session("bench", None).await.expect("failed to create session");
+    let (tx, mut rx) = mpsc::channel::<Message>(100);
+
+    let started = Instant::now();
+    manager.query(&session_id, "benchmark", None, tx).await.expect("query failed");
+
+    let mut received = 0;
+    while let Some(message) = rx.recv().await {
+        received += 1;
+        if message.summary.is_some() || message.diagnostic.is_some() {
+            break;
+        }
+    }
+    (received, started.elapsed())
+}
+
+async fn measure(sessions: usize) {
+    let manager = Arc::new(ClaudeManager::new(Arc::new(McpRegistry::new()), Arc::new(ProjectRegistry::new())));
+
+    let started = Instant::now();
+    let runs: Vec<_> = (0..sessions)
+        .map(|_| tokio::spawn(run_session(manager.clone())))
+        .collect();
+    let mut total = 0;
+    let mut slowest = Duration::ZERO;
+    for run in runs {
+        let (received, elapsed) = run.await.expect("session task panicked");
+        total += received;
+        slowest = slowest.max(elapsed);
+    }
+    let elapsed = started.elapsed();
+
+    println!(
+        "{:>3} sessions: {:>7} messages in {:>8.1?} ({:>9.0} msg/s, slowest session {:.1?})",
+        sessions,
+        total,
+        elapsed,
+        total as f64 / elapsed.as_secs_f64(),
+        slowest
+    );
+}
+
+#[tokio::main]
+async fn main() {
+    let args = parse_args();
+    let cli = args.cli.clone().unwrap_or_else(write_fake_cli);
+    std::env::set_var("FORGE_CLAUDE_BIN", &cli);
+    std::env::set_var("FORGE_BENCH_MESSAGES", args.messages.to_string());
+
+    measure(1).await;
+    measure(args.sessions).await;
+
+    if args.cli.is_none() {
+        let _ = std::fs::remove_file(cli);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::api::models::ClaudeCliStatus;
-use crate::core::error::{ErrorResponse, Result};
+use crate::core::claude::{parse_stats, CliCompatibility, ParseStats};
+use crate::core::error::{ErrorCode, ErrorResponse, Result};
 use std::env;
 use std::path::Path;
 use tokio::process::Command;
@@ -23,7 +24,8 @@ pub async fn check_claude_cli() -> Result<bool> {
             } else {
                 // Command exists but returned an error
                 let stderr = String::from_utf8_lossy(&output.stderr);
-                Err(ErrorResponse::new(format!("Claude CLI returned an error: {}", stderr)))
+                Err(ErrorResponse::new(ErrorCode::CliFailed, format!("Claude CLI returned an error: {}", stderr))
+                    .with_details(serde_json::json!({ "stderr": stderr })))
             }
         }
         Ok(Err(_)) => {
@@ -32,7 +34,7 @@ pub async fn check_claude_cli() -> Result<bool> {
         }
         Err(_) => {
             // Timeout occurred
-            Err(ErrorResponse::new("Claude CLI check timed out after 30 seconds"))
+            Err(ErrorResponse::new(ErrorCode::Timeout, "Claude CLI check timed out after 30 seconds"))
         }
     }
 }
@@ -44,6 +46,7 @@ pub async fn get_claude_cli_status() -> Result<ClaudeCliStatus> {
         version: None,
         authenticated: false,
         error: None,
+        compatibility: None,
     };
     
     // Check if claude command exists and get version with timeout
@@ -67,6 +70,7 @@ pub async fn get_claude_cli_status() -> Result<ClaudeCliStatus> {
                         status.version = Some(version_line.trim().to_string());
                     }
                 }
+                status.compatibility = status.version.as_deref().map(CliCompatibility::check);
                 
                 // Skip auth check for now - it's too slow
                 // We'll check authentication lazily when actually using the CLI
@@ -156,7 +160,12 @@ pub async fn check_claude_auth() -> Result<bool> {
             Ok(false)
         }
         Err(_) => {
-            Err(ErrorResponse::new("Authentication check timed out"))
+            Err(ErrorResponse::new(ErrorCode::Timeout, "Authentication check timed out"))
         }
     }
-}
\ No newline at end of file
+}
+
+#[tauri::command]
+pub async fn get_cli_parse_stats() -> Result<ParseStats> {
+    Ok(parse_stats())
+}
@@ -0,0 +1,55 @@
+use crate::api::http::{new_token, ControlServerConfig, ControlServerInfo, ServerContext};
+use crate::core::error::{ErrorCode, ErrorResponse, Result};
+use crate::infrastructure::state::AppState;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+/// Start the localhost control API and turn it on for later launches. Keeps the saved
+/// port and token unless `port` is given or `rotate_token` is set.
+#[tauri::command]
+pub async fn start_control_server(
+    port: Option<u16>,
+    rotate_token: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ControlServerInfo> {
+    let config_dir = config_dir(&app)?;
+    let mut config = ControlServerConfig::load(&config_dir).map_err(ErrorResponse::from)?;
+    if let Some(port) = port {
+        config.port = port;
+    }
+    if rotate_token.unwrap_or(false) {
+        config.token = new_token();
+    }
+
+    let context = ServerContext::new(app.clone(), &state);
+    let info = state.control_server.start(context, config.port, config.token.clone()).await
+        .map_err(ErrorResponse::from)?;
+
+    config.enabled = true;
+    config.port = info.port;
+    config.save(&config_dir).map_err(ErrorResponse::from)?;
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn stop_control_server(app: AppHandle, state: State<'_, AppState>) -> Result<()> {
+    state.control_server.stop();
+
+    let config_dir = config_dir(&app)?;
+    let mut config = ControlServerConfig::load(&config_dir).map_err(ErrorResponse::from)?;
+    config.enabled = false;
+    config.save(&config_dir).map_err(ErrorResponse::from)
+}
+
+/// URL and token of the running control API, or `None` if it is off
+#[tauri::command]
+pub async fn get_control_server_status(state: State<'_, AppState>) -> Result<Option<ControlServerInfo>> {
+    Ok(state.control_server.info())
+}
+
+pub(super) fn config_dir(app: &AppHandle) -> Result<PathBuf> {
+    app.path()
+        .app_config_dir()
+        .map_err(|e| ErrorResponse::new(ErrorCode::Io, format!("No config directory: {}", e)))
+}
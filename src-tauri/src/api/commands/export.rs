@@ -0,0 +1,101 @@
+use crate::api::models::CreateSessionResponse;
+use crate::core::claude::{ConversationExport, ExportFormat};
+use crate::core::error::{ErrorCode, ErrorResponse, Result};
+use crate::infrastructure::state::AppState;
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+use tauri_plugin_dialog::{DialogExt, FilePath};
+use tokio::sync::oneshot;
+use tracing::info;
+
+/// Wait for a file dialog opened with `open` to close
+pub(super) async fn dialog_path(open: impl FnOnce(oneshot::Sender<Option<FilePath>>)) -> Result<Option<PathBuf>> {
+    let (tx, rx) = oneshot::channel();
+    open(tx);
+    match rx.await.ok().flatten() {
+        Some(path) => path
+            .into_path()
+            .map(Some)
+            .map_err(|e| ErrorResponse::new(ErrorCode::InvalidInput, e.to_string())),
+        None => Ok(None),
+    }
+}
+
+/// Export a session to `path`, asking the user where to save it if no path is given.
+/// Returns the path written, or `None` if the user cancelled.
+#[tauri::command]
+pub async fn export_conversation(
+    session_id: String,
+    format: ExportFormat,
+    path: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<String>> {
+    let export = {
+        state.claude_manager.export_session(&session_id).await
+            .map_err(ErrorResponse::from)?
+    };
+
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let file_name = format!("conversation-{}.{}", session_id, format.extension());
+            let chosen = dialog_path(|tx| {
+                app.dialog()
+                    .file()
+                    .add_filter(format.label(), &[format.extension()])
+                    .set_file_name(file_name)
+                    .save_file(move |path| {
+                        let _ = tx.send(path);
+                    })
+            })
+            .await?;
+            match chosen {
+                Some(path) => path,
+                None => {
+                    info!("Export of session {} cancelled", session_id);
+                    return Ok(None);
+                }
+            }
+        }
+    };
+
+    export.write(format, &path)
+        .map_err(ErrorResponse::from)?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// Restore a JSON export as a new session, asking the user for the file if no path is given
+#[tauri::command]
+pub async fn import_conversation(
+    path: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<CreateSessionResponse>> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let chosen = dialog_path(|tx| {
+                app.dialog()
+                    .file()
+                    .add_filter(ExportFormat::Json.label(), &[ExportFormat::Json.extension()])
+                    .pick_file(move |path| {
+                        let _ = tx.send(path);
+                    })
+            })
+            .await?;
+            match chosen {
+                Some(path) => path,
+                None => return Ok(None),
+            }
+        }
+    };
+
+    let export = ConversationExport::read(&path)
+        .map_err(ErrorResponse::from)?;
+
+    match state.claude_manager.import_conversation(export).await {
+        Ok(session_id) => Ok(Some(CreateSessionResponse { session_id })),
+        Err(e) => Err(ErrorResponse::from(e)),
+    }
+}
@@ -1,32 +1,205 @@
+use crate::api::commands::start_query;
+use crate::api::models::MergeBranchResponse;
+use crate::core::claude::QueryOptions;
+use crate::core::error::{ErrorCode, ErrorResponse, Result};
 use crate::core::git::{
-    check_status, checkout_branch, create_branch, stash_changes,
-    GitCheckoutOptions, GitStatus,
+    check_merge as check_repo_merge, check_status, checked_out_at, checkout_branch, create_branch,
+    list_checkpoints as list_repo_checkpoints, merge_branch as merge_repo_branch, resolution_prompt,
+    restore_checkpoint as restore_repo_checkpoint, stash_changes, toplevel, Checkpoint,
+    GitCheckoutOptions, GitStatus, MergeMethod, MergeOptions, MergeReport,
 };
+use crate::infrastructure::state::AppState;
+use tauri::{Manager, State};
+use tracing::info;
 
 #[tauri::command]
-pub async fn check_git_status(directory: String) -> Result<GitStatus, String> {
-    check_status(&directory).map_err(|e| e.to_string())
+pub async fn check_git_status(
+    directory: Option<String>,
+    project_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<GitStatus> {
+    let directory = resolve_directory(&state, directory, project_id)?;
+    Ok(check_status(&directory)?)
 }
 
 #[tauri::command]
 pub async fn git_checkout_branch(
-    directory: String,
+    directory: Option<String>,
+    project_id: Option<String>,
     branch: String,
     options: Option<GitCheckoutOptions>,
-) -> Result<(), String> {
-    checkout_branch(&directory, &branch, options).map_err(|e| e.to_string())
+    state: State<'_, AppState>,
+) -> Result<()> {
+    let directory = resolve_directory(&state, directory, project_id)?;
+    Ok(checkout_branch(&directory, &branch, options)?)
 }
 
 #[tauri::command]
 pub async fn git_create_branch(
-    directory: String,
+    directory: Option<String>,
+    project_id: Option<String>,
     branch_name: String,
     checkout: bool,
-) -> Result<(), String> {
-    create_branch(&directory, &branch_name, checkout).map_err(|e| e.to_string())
+    state: State<'_, AppState>,
+) -> Result<()> {
+    let directory = resolve_directory(&state, directory, project_id)?;
+    Ok(create_branch(&directory, &branch_name, checkout)?)
 }
 
 #[tauri::command]
-pub async fn git_stash_changes(directory: String, message: Option<String>) -> Result<(), String> {
-    stash_changes(&directory, message).map_err(|e| e.to_string())
-}
\ No newline at end of file
+pub async fn git_stash_changes(
+    directory: Option<String>,
+    project_id: Option<String>,
+    message: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    let directory = resolve_directory(&state, directory, project_id)?;
+    Ok(stash_changes(&directory, message)?)
+}
+
+/// Checkpoints taken before agent turns in the repository, newest first, optionally only a session's
+#[tauri::command]
+pub async fn list_checkpoints(
+    directory: Option<String>,
+    project_id: Option<String>,
+    session_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Checkpoint>> {
+    let directory = resolve_directory(&state, directory, project_id)?;
+    let mut checkpoints = list_repo_checkpoints(&directory)?;
+    if let Some(session_id) = session_id {
+        checkpoints.retain(|checkpoint| checkpoint.session_id.as_deref() == Some(session_id.as_str()));
+    }
+    Ok(checkpoints)
+}
+
+/// Roll the worktree back to a checkpoint. Returns the checkpoint saving the state it replaced.
+#[tauri::command]
+pub async fn restore_checkpoint(
+    directory: Option<String>,
+    project_id: Option<String>,
+    checkpoint_id: String,
+    state: State<'_, AppState>,
+) -> Result<Checkpoint> {
+    let directory = resolve_directory(&state, directory, project_id)?;
+    ensure_no_running_session(&state, &directory).await?;
+    Ok(restore_repo_checkpoint(&directory, &checkpoint_id)?)
+}
+
+/// Trial-merge `source` into `target` and report the conflicts, changing nothing
+#[tauri::command]
+pub async fn check_merge(
+    directory: Option<String>,
+    project_id: Option<String>,
+    source: String,
+    target: String,
+    state: State<'_, AppState>,
+) -> Result<MergeReport> {
+    let directory = resolve_directory(&state, directory, project_id)?;
+    Ok(check_repo_merge(&directory, &source, &target)?)
+}
+
+/// Merge an agent's branch into `target` if it merges cleanly. On conflicts nothing is merged,
+/// and with `resolution_agent_id` a session of that agent is started in the source branch's
+/// worktree to resolve them.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn merge_branch(
+    directory: Option<String>,
+    project_id: Option<String>,
+    source: String,
+    target: String,
+    options: Option<MergeOptions>,
+    resolution_agent_id: Option<String>,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+) -> Result<MergeBranchResponse> {
+    let directory = resolve_directory(&state, directory, project_id.clone())?;
+    let options = options.unwrap_or_default();
+    let method = options.method;
+
+    // The target is fast-forwarded where it is checked out, and a rebase runs where the source is
+    let mut busy = vec![checked_out_at(&directory, &target)?];
+    if method == MergeMethod::Rebase {
+        busy.push(checked_out_at(&directory, &source)?);
+    }
+    for worktree in busy.into_iter().flatten() {
+        ensure_no_running_session(&state, &worktree).await?;
+    }
+
+    let report = merge_repo_branch(&directory, &source, &target, options)?;
+    let resolution_session_id = match resolution_agent_id {
+        Some(agent_id) if !report.conflicts.is_empty() => {
+            let worktree = checked_out_at(&directory, &source)?.ok_or_else(|| {
+                ErrorResponse::new(
+                    ErrorCode::InvalidInput,
+                    format!("{} is not checked out in a worktree to resolve its conflicts in", source),
+                )
+            })?;
+            let session_id = state
+                .claude_manager
+                .create_session(&agent_id, project_id.as_deref())
+                .await
+                .map_err(ErrorResponse::from)?;
+            info!("Resolving conflicts between {} and {} in session {}", source, target, session_id);
+
+            state.subscriptions.subscribe(&session_id, window.label());
+            start_query(
+                window.app_handle().clone(),
+                state.claude_manager.clone(),
+                state.subscriptions.clone(),
+                session_id.clone(),
+                resolution_prompt(&report, method),
+                Some(QueryOptions {
+                    cwd: Some(worktree),
+                    ..Default::default()
+                }),
+            );
+            Some(session_id)
+        }
+        _ => None,
+    };
+
+    Ok(MergeBranchResponse {
+        report,
+        resolution_session_id,
+    })
+}
+
+/// Refuse to rewrite files in the repository containing `directory` while an agent runs there,
+/// which would leave both confused
+async fn ensure_no_running_session(state: &AppState, directory: &str) -> Result<()> {
+    let Some(root) = toplevel(directory) else {
+        return Ok(());
+    };
+    for session in state.claude_manager.list_sessions().await {
+        let in_repo = session.working_directory.as_deref().and_then(toplevel).as_ref() == Some(&root);
+        if session.state.is_active() && in_repo {
+            return Err(ErrorResponse::new(
+                ErrorCode::InvalidInput,
+                format!("Session {} is still running in {}", session.session_id, root),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The directory to run git in: `directory` as given, the project's root,
+/// or `directory` after checking it lies within the project
+fn resolve_directory(
+    state: &AppState,
+    directory: Option<String>,
+    project_id: Option<String>,
+) -> Result<String> {
+    match (project_id, directory) {
+        (Some(project_id), directory) => state
+            .project_registry
+            .resolve_directory(&project_id, directory.as_deref())
+            .map_err(ErrorResponse::from),
+        (None, Some(directory)) => Ok(directory),
+        (None, None) => Err(ErrorResponse::new(
+            ErrorCode::InvalidInput,
+            "Either directory or projectId is required",
+        )),
+    }
+}
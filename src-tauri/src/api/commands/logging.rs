@@ -0,0 +1,68 @@
+use crate::core::error::{ErrorCode, ErrorResponse, Result};
+use crate::core::logging::{self, LogFilter, LogRecord};
+use chrono::Local;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+
+use super::export::dialog_path;
+
+#[tauri::command]
+pub async fn get_log_level() -> Result<String> {
+    logging::level().ok_or_else(|| ErrorResponse::new(ErrorCode::Internal, "Logging is not initialized"))
+}
+
+/// Accepts a level ("debug") or `RUST_LOG`-style directives
+#[tauri::command]
+pub async fn set_log_level(level: String) -> Result<()> {
+    logging::set_level(&level).map_err(ErrorResponse::from)
+}
+
+/// Zip recent logs to `path`, asking the user where to save them if no path is given.
+/// Returns the path written, or `None` if the user cancelled.
+#[tauri::command]
+pub async fn create_log_bundle(path: Option<String>, app: AppHandle) -> Result<Option<String>> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let file_name = format!("forge-logs-{}.zip", Local::now().format("%Y%m%d-%H%M%S"));
+            let chosen = dialog_path(|tx| {
+                app.dialog()
+                    .file()
+                    .add_filter("Zip archive", &["zip"])
+                    .set_file_name(file_name)
+                    .save_file(move |path| {
+                        let _ = tx.send(path);
+                    })
+            })
+            .await?;
+            match chosen {
+                Some(path) => path,
+                None => return Ok(None),
+            }
+        }
+    };
+
+    logging::create_bundle(&path).map_err(ErrorResponse::from)?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// Start streaming records matching `filter` to this window as `log-record` events.
+/// Returns the most recent buffered matches so the viewer can backfill.
+#[tauri::command]
+pub async fn subscribe_logs(
+    filter: Option<LogFilter>,
+    limit: Option<usize>,
+    window: tauri::Window,
+) -> Result<Vec<LogRecord>> {
+    let filter = filter.unwrap_or_default();
+    let backfill = logging::recent_records(&filter, limit)
+        .map_err(ErrorResponse::from)?;
+    logging::set_stream_filter(window.label(), Some(filter)).map_err(ErrorResponse::from)?;
+    Ok(backfill)
+}
+
+#[tauri::command]
+pub async fn unsubscribe_logs(window: tauri::Window) -> Result<()> {
+    logging::set_stream_filter(window.label(), None).map_err(ErrorResponse::from)
+}
@@ -0,0 +1,55 @@
+use crate::core::error::{ErrorCode, ErrorResponse, Result};
+use crate::core::mcp::{check_server, McpHealth, McpServer};
+use crate::infrastructure::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub async fn list_mcp_servers(state: State<'_, AppState>) -> Result<Vec<McpServer>> {
+    Ok(state.mcp_registry.list_servers())
+}
+
+#[tauri::command]
+pub async fn save_mcp_server(
+    server: McpServer,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    state.mcp_registry.save_server(server)
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn remove_mcp_server(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    state.mcp_registry.remove_server(&name)
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_agent_mcp_servers(
+    agent_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<McpServer>> {
+    Ok(state.mcp_registry.agent_servers(&agent_id))
+}
+
+#[tauri::command]
+pub async fn set_agent_mcp_servers(
+    agent_id: String,
+    server_names: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    state.mcp_registry.set_agent_servers(&agent_id, server_names)
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn check_mcp_server(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<McpHealth> {
+    let server = state.mcp_registry.get_server(&name).ok_or_else(|| ErrorResponse::new(ErrorCode::NotFound, format!("MCP server not found: {}", name)))?;
+
+    Ok(check_server(&server).await)
+}
@@ -1,9 +1,45 @@
 mod cli_check;
+mod control;
+mod export;
 mod git;
 mod greet;
+mod logging;
+mod mcp;
+mod projects;
+mod search;
 mod session;
+mod templates;
 
-pub use cli_check::{check_claude_auth, check_claude_cli, get_claude_cli_status, quick_claude_check};
-pub use git::{check_git_status, git_checkout_branch, git_create_branch, git_stash_changes};
+pub use cli_check::{
+    check_claude_auth, check_claude_cli, get_claude_cli_status, get_cli_parse_stats,
+    quick_claude_check,
+};
+pub use control::{get_control_server_status, start_control_server, stop_control_server};
+pub use export::{export_conversation, import_conversation};
+pub use git::{
+    check_git_status, check_merge, git_checkout_branch, git_create_branch, git_stash_changes,
+    list_checkpoints, merge_branch, restore_checkpoint,
+};
 pub use greet::greet;
-pub use session::{abort_session, clear_session, create_session, send_message};
\ No newline at end of file
+pub use logging::{
+    create_log_bundle, get_log_level, set_log_level, subscribe_logs, unsubscribe_logs,
+};
+pub use mcp::{
+    check_mcp_server, get_agent_mcp_servers, list_mcp_servers, remove_mcp_server,
+    save_mcp_server, set_agent_mcp_servers,
+};
+pub use projects::{
+    add_project_worktree, get_project, list_projects, list_recent_projects, open_project,
+    remove_project, remove_project_agent, save_project_agent, update_project,
+};
+pub use search::search_sessions;
+pub use session::{
+    abort_session, clear_session, create_session, fork_session, get_messages_since, get_session,
+    get_session_turns, import_cli_session, list_cli_sessions, list_sessions, send_message, subscribe_session,
+    unsubscribe_session,
+};
+pub use templates::{
+    agent_from_template, export_agent_template, import_agent_template, list_agent_templates,
+    remove_agent_template,
+};
+pub(crate) use session::start_query;
\ No newline at end of file
@@ -0,0 +1,137 @@
+use crate::core::claude::AgentConfig;
+use crate::core::error::{ErrorResponse, Result};
+use crate::core::git;
+use crate::core::projects::{Project, ProjectUpdate, ProjectWorktree};
+use crate::infrastructure::state::AppState;
+use chrono::Utc;
+use std::path::Path;
+use tauri::{AppHandle, State};
+use tauri_plugin_dialog::DialogExt;
+
+use super::export::dialog_path;
+
+/// Projects shown when no limit is given
+const DEFAULT_RECENT_PROJECTS: usize = 10;
+
+/// Open the project rooted at `path`, asking for the folder if no path is given.
+/// Creates the project the first time a folder is opened. Returns `None` if the user cancelled.
+#[tauri::command]
+pub async fn open_project(
+    path: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<Project>> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let chosen = dialog_path(|tx| {
+                app.dialog().file().pick_folder(move |path| {
+                    let _ = tx.send(path);
+                })
+            })
+            .await?;
+            match chosen {
+                Some(path) => path.to_string_lossy().into_owned(),
+                None => return Ok(None),
+            }
+        }
+    };
+
+    state.project_registry.open(&path)
+        .map(Some)
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_project(project_id: String, state: State<'_, AppState>) -> Result<Project> {
+    state.project_registry.get(&project_id)
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn list_projects(state: State<'_, AppState>) -> Result<Vec<Project>> {
+    Ok(state.project_registry.list())
+}
+
+/// Most recently opened projects first
+#[tauri::command]
+pub async fn list_recent_projects(
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Project>> {
+    Ok(state.project_registry.recent(limit.unwrap_or(DEFAULT_RECENT_PROJECTS)))
+}
+
+#[tauri::command]
+pub async fn update_project(
+    project_id: String,
+    update: ProjectUpdate,
+    state: State<'_, AppState>,
+) -> Result<Project> {
+    state.project_registry.update(&project_id, update)
+        .map_err(ErrorResponse::from)
+}
+
+/// Forget a project without touching its files
+#[tauri::command]
+pub async fn remove_project(project_id: String, state: State<'_, AppState>) -> Result<()> {
+    state.project_registry.remove(&project_id)
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn save_project_agent(
+    project_id: String,
+    agent: AgentConfig,
+    state: State<'_, AppState>,
+) -> Result<Project> {
+    state.project_registry.save_agent(&project_id, agent)
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn remove_project_agent(
+    project_id: String,
+    agent_id: String,
+    state: State<'_, AppState>,
+) -> Result<Project> {
+    state.project_registry.remove_agent(&project_id, &agent_id)
+        .map_err(ErrorResponse::from)
+}
+
+/// Create a worktree on a new branch for the project. `path` is relative to the project root
+/// and defaults to a folder in the project's worktree directory; `base` defaults to the
+/// project's default branch.
+#[tauri::command]
+pub async fn add_project_worktree(
+    project_id: String,
+    branch: String,
+    path: Option<String>,
+    base: Option<String>,
+    agent_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Project> {
+    let project = state.project_registry.get(&project_id)
+        .map_err(ErrorResponse::from)?;
+    let path = match path {
+        Some(path) => Path::new(&project.root_path).join(path),
+        None => project.worktree_path(&branch),
+    };
+    let path = path.to_string_lossy().into_owned();
+    let base = base.or_else(|| project.default_branch.clone());
+
+    git::add_worktree(&project.root_path, &path, &branch, base.as_deref())?;
+
+    state
+        .project_registry
+        .add_worktree(
+            &project_id,
+            ProjectWorktree {
+                path,
+                branch,
+                agent_id,
+                created_at: Utc::now(),
+            },
+        )
+        .map_err(ErrorResponse::from)
+}
@@ -0,0 +1,16 @@
+use crate::core::error::Result;
+use crate::core::search::{SearchHit, SearchQuery};
+use crate::infrastructure::state::AppState;
+use tauri::State;
+use tracing::debug;
+
+#[tauri::command]
+pub async fn search_sessions(
+    query: SearchQuery,
+    state: State<'_, AppState>,
+) -> Result<Vec<SearchHit>> {
+    let claude_manager = state.claude_manager.clone();
+    let hits = claude_manager.search(&query).await;
+    debug!("Search for {:?} returned {} hits", query.query, hits.len());
+    Ok(hits)
+}
@@ -1,22 +1,54 @@
 use crate::api::models::CreateSessionResponse;
-use crate::core::claude::QueryOptions;
+use crate::core::claude::{
+    self, ClaudeManager, CliSessionInfo, ForkSessionOptions, ForkedSession, Message, QueryOptions,
+    SessionInfo, TurnSummary,
+};
 use crate::core::error::{ErrorResponse, Result};
 use crate::infrastructure::state::AppState;
+use crate::infrastructure::subscriptions::SessionSubscriptions;
 use std::sync::Arc;
-use tauri::{Emitter, State};
+use tauri::{AppHandle, Manager, State};
 use tokio::sync::mpsc;
-use tokio::sync::Mutex;
-use tracing::{debug, error, info};
+use tracing::{debug, info, warn, Instrument};
 
 #[tauri::command]
 pub async fn create_session(
     agent_id: String,
-    state: State<'_, Arc<Mutex<AppState>>>,
+    project_id: Option<String>,
+    state: State<'_, AppState>,
 ) -> Result<CreateSessionResponse> {
-    let state = state.lock().await;
-    match state.claude_manager.create_session(&agent_id).await {
+    match state.claude_manager.create_session(&agent_id, project_id.as_deref()).await {
         Ok(session_id) => Ok(CreateSessionResponse { session_id }),
-        Err(e) => Err(ErrorResponse::new(e.to_string())),
+        Err(e) => Err(ErrorResponse::from(e)),
+    }
+}
+
+#[tauri::command]
+pub async fn get_session(session_id: String, state: State<'_, AppState>) -> Result<SessionInfo> {
+    state.claude_manager.session_info(&session_id).await
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn list_sessions(state: State<'_, AppState>) -> Result<Vec<SessionInfo>> {
+    Ok(state.claude_manager.list_sessions().await)
+}
+
+#[tauri::command]
+pub async fn list_cli_sessions(working_directory: Option<String>) -> Result<Vec<CliSessionInfo>> {
+    claude::list_cli_sessions(working_directory.as_deref())
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn import_cli_session(
+    agent_id: String,
+    cli_session_id: String,
+    state: State<'_, AppState>,
+) -> Result<CreateSessionResponse> {
+    match state.claude_manager.import_cli_session(&agent_id, &cli_session_id).await {
+        Ok(session_id) => Ok(CreateSessionResponse { session_id }),
+        Err(e) => Err(ErrorResponse::from(e)),
     }
 }
 
@@ -26,18 +58,40 @@ pub async fn send_message(
     prompt: String,
     options: Option<QueryOptions>,
     window: tauri::Window,
-    state: State<'_, Arc<Mutex<AppState>>>,
+    state: State<'_, AppState>,
 ) -> Result<()> {
     info!("send_message called - session_id: {}, prompt_length: {}", session_id, prompt.len());
     debug!("Prompt: {}", prompt);
-    
-    let state = state.lock().await;
+
+    // The sending window follows the session; other windows can subscribe to it as well
+    state.subscriptions.subscribe(&session_id, window.label());
+
+    start_query(
+        window.app_handle().clone(),
+        state.claude_manager.clone(),
+        state.subscriptions.clone(),
+        session_id,
+        prompt,
+        options,
+    );
+    Ok(())
+}
+
+/// Run a query in the background, routing its messages to the windows following the session
+pub(crate) fn start_query(
+    app: AppHandle,
+    claude_manager: Arc<ClaudeManager>,
+    subscriptions: Arc<SessionSubscriptions>,
+    session_id: String,
+    prompt: String,
+    options: Option<QueryOptions>,
+) {
     let (tx, mut rx) = mpsc::channel(100);
 
     // Clone values for the spawned task
     let session_id_clone = session_id.clone();
-    let window_clone = window.clone();
-    let claude_manager = state.claude_manager.clone();
+    let app_clone = app.clone();
+    let subscriptions_clone = subscriptions.clone();
 
     // Spawn task to handle the query
     tokio::spawn(async move {
@@ -45,48 +99,114 @@ pub async fn send_message(
             .query(&session_id_clone, &prompt, options, tx)
             .await
         {
-            let _ = window_clone.emit("claude-error", ErrorResponse::new(e.to_string()));
+            subscriptions_clone.emit(&app_clone, &session_id_clone, "claude-error", ErrorResponse::from(e));
         }
     });
 
     // Spawn task to handle message forwarding
+    let forward_span = crate::span!("forward_messages", session_id = %session_id);
     tokio::spawn(async move {
         info!("Started message forwarding task");
         let mut forwarded_count = 0;
         
         while let Some(message) = rx.recv().await {
             forwarded_count += 1;
-            debug!("Forwarding message #{} to frontend: role={}, content_length={}", 
-                forwarded_count, message.role, message.content.len());
+            debug!("Forwarding message #{} to frontend: role={}, seq={}, content_length={}", 
+                forwarded_count, message.role, message.seq, message.content.len());
             
-            match window.emit("claude-message", &message) {
-                Ok(_) => debug!("Message forwarded successfully"),
-                Err(e) => error!("Failed to emit message to frontend: {}", e),
+            if let Some(diagnostic) = &message.diagnostic {
+                subscriptions.emit(&app, &session_id, "claude-diagnostic", diagnostic);
+            }
+            
+            if let Some(summary) = &message.summary {
+                subscriptions.emit(&app, &session_id, "claude-turn-summary", summary);
+            }
+            
+            if !subscriptions.emit(&app, &session_id, "claude-message", &message) {
+                warn!("Message {} was not delivered everywhere; windows can recover it with get_messages_since", message.seq);
             }
         }
         
         info!("Message forwarding task completed after {} messages", forwarded_count);
-    });
+    }.instrument(forward_span));
+}
+
+/// Follow a session's events from this window. Returns the messages after `since_seq`
+/// (all of them if it is not given) so a reloaded or newly opened window can catch up;
+/// a message may arrive both in the replay and as an event, so dedupe by `seq`.
+#[tauri::command]
+pub async fn subscribe_session(
+    session_id: String,
+    since_seq: Option<u64>,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+) -> Result<Vec<Message>> {
+    // Subscribe before reading the history so nothing falls in between
+    state.subscriptions.subscribe(&session_id, window.label());
+    match state.claude_manager.messages_since(&session_id, since_seq).await {
+        Ok(messages) => Ok(messages),
+        Err(e) => {
+            state.subscriptions.unsubscribe(&session_id, window.label());
+            Err(ErrorResponse::from(e))
+        }
+    }
+}
+
+/// Stored messages after `seq`, for filling a gap in the sequence a window received
+#[tauri::command]
+pub async fn get_messages_since(
+    session_id: String,
+    seq: u64,
+    state: State<'_, AppState>,
+) -> Result<Vec<Message>> {
+    state.claude_manager.messages_since(&session_id, Some(seq)).await
+        .map_err(ErrorResponse::from)
+}
 
+/// The session's finished queries, each with the files it touched and the commands it ran
+#[tauri::command]
+pub async fn get_session_turns(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<TurnSummary>> {
+    state.claude_manager.turns(&session_id).await
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn unsubscribe_session(
+    session_id: String,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    state.subscriptions.unsubscribe(&session_id, window.label());
     Ok(())
 }
 
+#[tauri::command]
+pub async fn fork_session(
+    session_id: String,
+    options: Option<ForkSessionOptions>,
+    state: State<'_, AppState>,
+) -> Result<ForkedSession> {
+    state.claude_manager.fork_session(&session_id, options.unwrap_or_default()).await
+        .map_err(ErrorResponse::from)
+}
+
 #[tauri::command]
 pub async fn abort_session(
     session_id: String,
-    state: State<'_, Arc<Mutex<AppState>>>,
+    state: State<'_, AppState>,
 ) -> Result<()> {
-    let state = state.lock().await;
     state.claude_manager.abort_session(&session_id).await
-        .map_err(|e| ErrorResponse::new(e.to_string()))
+        .map_err(ErrorResponse::from)
 }
 
 #[tauri::command]
 pub async fn clear_session(
     session_id: String,
-    state: State<'_, Arc<Mutex<AppState>>>,
+    state: State<'_, AppState>,
 ) -> Result<()> {
-    let state = state.lock().await;
     state.claude_manager.clear_session(&session_id).await
-        .map_err(|e| ErrorResponse::new(e.to_string()))
+        .map_err(ErrorResponse::from)
 }
\ No newline at end of file
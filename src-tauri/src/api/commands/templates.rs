@@ -0,0 +1,108 @@
+use crate::core::agents::{AgentTemplate, TemplateEntry, TemplateFormat, TemplateLibrary};
+use crate::core::claude::AgentConfig;
+use crate::core::error::{ErrorResponse, Result};
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+use tracing::info;
+use uuid::Uuid;
+
+use super::control::config_dir;
+use super::export::dialog_path;
+
+fn library(app: &AppHandle) -> Result<TemplateLibrary> {
+    Ok(TemplateLibrary::new(config_dir(app)?.join("agent-templates")))
+}
+
+/// Built-in and user templates, user ones replacing built-in ones with the same id
+#[tauri::command]
+pub async fn list_agent_templates(app: AppHandle) -> Result<Vec<TemplateEntry>> {
+    Ok(library(&app)?.list())
+}
+
+/// A new agent config with a template's settings
+#[tauri::command]
+pub async fn agent_from_template(
+    template_id: String,
+    name: Option<String>,
+    app: AppHandle,
+) -> Result<AgentConfig> {
+    let entry = library(&app)?.get(&template_id)
+        .map_err(ErrorResponse::from)?;
+    let agent_id = format!("{}-{}", template_id, Uuid::new_v4().simple());
+    Ok(entry.template.agent_config(&agent_id, name))
+}
+
+/// Add a TOML or JSON template to the user's library, asking for the file if no path is given.
+/// Returns `None` if the user cancelled.
+#[tauri::command]
+pub async fn import_agent_template(path: Option<String>, app: AppHandle) -> Result<Option<AgentTemplate>> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let chosen = dialog_path(|tx| {
+                app.dialog()
+                    .file()
+                    .add_filter("Agent template", &[TemplateFormat::Toml.extension(), TemplateFormat::Json.extension()])
+                    .pick_file(move |path| {
+                        let _ = tx.send(path);
+                    })
+            })
+            .await?;
+            match chosen {
+                Some(path) => path,
+                None => return Ok(None),
+            }
+        }
+    };
+
+    library(&app)?.import(&path)
+        .map(Some)
+        .map_err(ErrorResponse::from)
+}
+
+/// Save a template to `path`, asking the user where if no path is given. The format follows
+/// the file's extension. Returns the path written, or `None` if the user cancelled.
+#[tauri::command]
+pub async fn export_agent_template(
+    template_id: String,
+    format: Option<TemplateFormat>,
+    path: Option<String>,
+    app: AppHandle,
+) -> Result<Option<String>> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let format = format.unwrap_or(TemplateFormat::Toml);
+            let file_name = format!("{}.{}", template_id, format.extension());
+            let chosen = dialog_path(|tx| {
+                app.dialog()
+                    .file()
+                    .add_filter(format.label(), &[format.extension()])
+                    .set_file_name(file_name)
+                    .save_file(move |path| {
+                        let _ = tx.send(path);
+                    })
+            })
+            .await?;
+            match chosen {
+                Some(path) => path,
+                None => {
+                    info!("Export of agent template {} cancelled", template_id);
+                    return Ok(None);
+                }
+            }
+        }
+    };
+
+    library(&app)?.export(&template_id, &path)
+        .map_err(ErrorResponse::from)?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// Delete a user template; built-in templates can't be removed
+#[tauri::command]
+pub async fn remove_agent_template(template_id: String, app: AppHandle) -> Result<()> {
+    library(&app)?.remove(&template_id)
+        .map_err(ErrorResponse::from)
+}
@@ -0,0 +1,68 @@
+use axum::extract::{Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+use tracing::warn;
+
+use super::ServerState;
+use crate::core::error::{ErrorCode, ErrorResponse};
+
+/// Accept requests carrying the token as `Authorization: Bearer <token>`, or as
+/// `?token=<token>` for WebSocket clients that can't set headers
+pub async fn require_token(State(state): State<Arc<ServerState>>, request: Request, next: Next) -> Response {
+    let header = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let query = request
+        .uri()
+        .query()
+        .and_then(|query| query.split('&').find_map(|pair| pair.strip_prefix("token=")));
+
+    match header.or(query) {
+        Some(token) if tokens_match(token, &state.token) => next.run(request).await,
+        _ => {
+            warn!("Rejected control API request to {} without a valid token", request.uri().path());
+            ErrorResponse::new(ErrorCode::Unauthorized, "Missing or invalid token").into_response()
+        }
+    }
+}
+
+/// Compare in constant time so the token can't be guessed byte by byte
+fn tokens_match(provided: &str, expected: &str) -> bool {
+    provided.len() == expected.len()
+        && provided
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_only_the_same_token() {
+        assert!(tokens_match("3f9a0c7e", "3f9a0c7e"));
+        assert!(!tokens_match("3f9a0c7f", "3f9a0c7e"));
+        assert!(!tokens_match("3F9A0C7E", "3f9a0c7e"));
+    }
+
+    #[test]
+    fn prefixes_and_extensions_do_not_match() {
+        assert!(!tokens_match("3f9a", "3f9a0c7e"));
+        assert!(!tokens_match("3f9a0c7e00", "3f9a0c7e"));
+        assert!(!tokens_match("", "3f9a0c7e"));
+    }
+
+    #[test]
+    fn compares_bytes_not_characters() {
+        // Same byte length, different characters
+        assert!(!tokens_match("é", "ab"));
+        assert!(tokens_match("tökén", "tökén"));
+        assert!(!tokens_match("tökén", "tokén"));
+    }
+}
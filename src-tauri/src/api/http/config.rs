@@ -0,0 +1,63 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+const CONFIG_FILE: &str = "control-server.json";
+
+/// Saved control server settings. Editor extensions read the port and token from this file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlServerConfig {
+    /// Start the server with the app
+    pub enabled: bool,
+    /// 0 picks a free port, which is then kept
+    pub port: u16,
+    pub token: String,
+}
+
+impl Default for ControlServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 0,
+            token: new_token(),
+        }
+    }
+}
+
+impl ControlServerConfig {
+    pub fn path(config_dir: &Path) -> PathBuf {
+        config_dir.join(CONFIG_FILE)
+    }
+
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        let path = Self::path(config_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Save readable by the current user only, since the file holds the token
+    pub fn save(&self, config_dir: &Path) -> Result<()> {
+        fs::create_dir_all(config_dir)?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options.open(Self::path(config_dir))?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+pub fn new_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
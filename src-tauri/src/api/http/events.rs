@@ -0,0 +1,122 @@
+use axum::extract::ws::{Message as WsMessage, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+use super::ServerState;
+use crate::core::error::{ErrorCode, ErrorResponse};
+use crate::infrastructure::subscriptions::SessionEvent;
+
+/// What a client may send over the socket
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+enum ClientMessage {
+    /// Follow a session, replaying its messages after `since_seq` (all of them if not given)
+    Subscribe { session_id: String, since_seq: Option<u64> },
+    Unsubscribe { session_id: String },
+}
+
+/// Stream session events as `{ sessionId, event, payload }`, the same events windows receive.
+/// Clients get every session until they subscribe to specific ones.
+pub async fn follow(ws: WebSocketUpgrade, State(state): State<Arc<ServerState>>) -> Response {
+    ws.on_upgrade(move |socket| forward_events(socket, state))
+}
+
+async fn forward_events(mut socket: WebSocket, state: Arc<ServerState>) {
+    let context = &state.context;
+    let mut events = context.subscriptions.subscribe_events();
+    let mut state_changes = context.claude_manager.subscribe_state_changes();
+    let mut shutdown = state.shutdown.clone();
+    let mut sessions: Option<HashSet<String>> = None;
+    debug!("Control API client connected to the event stream");
+
+    loop {
+        let event = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => {
+                    // Clients fill the gap from the messages endpoint, by seq
+                    warn!("Event stream client fell behind, skipped {} events", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            change = state_changes.recv() => match change {
+                Ok(change) => SessionEvent::new(&change.session_id, "session-state-changed", &change),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(WsMessage::Text(text))) => {
+                    let replies = handle_client_message(&text, &state, &mut sessions).await;
+                    if send_all(&mut socket, &replies).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+            // The only change is to stopped, and dropping the sender stops too
+            _ = shutdown.changed() => break,
+        };
+
+        if sessions.as_ref().is_some_and(|sessions| !sessions.contains(&event.session_id)) {
+            continue;
+        }
+        if send_all(&mut socket, &[event]).await.is_err() {
+            break;
+        }
+    }
+
+    let _ = socket.send(WsMessage::Close(None)).await;
+    debug!("Control API client left the event stream");
+}
+
+/// Apply a client message, returning what to send back
+async fn handle_client_message(
+    text: &str,
+    state: &ServerState,
+    sessions: &mut Option<HashSet<String>>,
+) -> Vec<SessionEvent> {
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(message) => message,
+        Err(e) => return vec![error_event(None, ErrorResponse::new(ErrorCode::InvalidInput, e.to_string()))],
+    };
+
+    match message {
+        ClientMessage::Subscribe { session_id, since_seq } => {
+            // Events that arrive while replaying are sent as well; dedupe by seq
+            match state.context.claude_manager.messages_since(&session_id, since_seq).await {
+                Ok(messages) => {
+                    sessions.get_or_insert_with(HashSet::new).insert(session_id.clone());
+                    messages
+                        .iter()
+                        .map(|message| SessionEvent::new(&session_id, "claude-message", message))
+                        .collect()
+                }
+                Err(e) => vec![error_event(Some(&session_id), ErrorResponse::from(e))],
+            }
+        }
+        ClientMessage::Unsubscribe { session_id } => {
+            sessions.get_or_insert_with(HashSet::new).remove(&session_id);
+            Vec::new()
+        }
+    }
+}
+
+fn error_event(session_id: Option<&str>, error: ErrorResponse) -> SessionEvent {
+    SessionEvent::new(session_id.unwrap_or_default(), "control-error", error)
+}
+
+async fn send_all(socket: &mut WebSocket, events: &[SessionEvent]) -> Result<(), axum::Error> {
+    for event in events {
+        let text = serde_json::to_string(event).unwrap_or_default();
+        socket.send(WsMessage::Text(text.into())).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,165 @@
+mod auth;
+mod config;
+mod events;
+mod routes;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+pub use config::{new_token, ControlServerConfig};
+
+use crate::core::claude::ClaudeManager;
+use crate::core::error::{ErrorCode, ErrorResponse};
+use crate::core::projects::ProjectRegistry;
+use crate::infrastructure::state::AppState;
+use crate::infrastructure::subscriptions::SessionSubscriptions;
+
+/// What the handlers share with the Tauri commands
+#[derive(Clone)]
+pub struct ServerContext {
+    pub app: AppHandle,
+    pub claude_manager: Arc<ClaudeManager>,
+    pub project_registry: Arc<ProjectRegistry>,
+    pub subscriptions: Arc<SessionSubscriptions>,
+}
+
+impl ServerContext {
+    pub fn new(app: AppHandle, state: &AppState) -> Self {
+        Self {
+            app,
+            claude_manager: state.claude_manager.clone(),
+            project_registry: state.project_registry.clone(),
+            subscriptions: state.subscriptions.clone(),
+        }
+    }
+}
+
+struct ServerState {
+    context: ServerContext,
+    token: String,
+    /// Flips to true when the server stops, closing open event streams
+    shutdown: watch::Receiver<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlServerInfo {
+    pub url: String,
+    pub port: u16,
+    pub token: String,
+}
+
+/// How long a restart waits for the old server to close its connections and release the port
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct RunningServer {
+    info: ControlServerInfo,
+    shutdown: watch::Sender<bool>,
+    /// Owns the listener; the port is free once it has finished
+    task: JoinHandle<()>,
+}
+
+/// Opt-in HTTP and WebSocket API on localhost for editor extensions and scripts
+#[derive(Default)]
+pub struct ControlServer {
+    running: Mutex<Option<RunningServer>>,
+}
+
+impl ControlServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Listen on 127.0.0.1:`port` (any free port for 0), replacing a running server
+    pub async fn start(&self, context: ServerContext, port: u16, token: String) -> anyhow::Result<ControlServerInfo> {
+        // Rebinding the same port right away would fail while the old listener is still open
+        if let Some(server) = self.signal_stop() {
+            let mut task = server.task;
+            if tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut task).await.is_err() {
+                warn!("Control server did not stop within {:?}, aborting it", SHUTDOWN_TIMEOUT);
+                task.abort();
+                let _ = task.await;
+            }
+        }
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await?;
+        let port = listener.local_addr()?.port();
+        let info = ControlServerInfo {
+            url: format!("http://127.0.0.1:{}", port),
+            port,
+            token: token.clone(),
+        };
+
+        let (shutdown, stopped) = watch::channel(false);
+        let router = routes::router(Arc::new(ServerState {
+            context,
+            token,
+            shutdown: stopped.clone(),
+        }));
+        let task = tokio::spawn(async move {
+            let mut stopped = stopped;
+            let server = axum::serve(listener, router).with_graceful_shutdown(async move {
+                // Also stops if the server is dropped without `stop`
+                let _ = stopped.wait_for(|stopped| *stopped).await;
+            });
+            if let Err(e) = server.await {
+                error!("Control server failed: {}", e);
+            }
+            info!("Control server stopped");
+        });
+
+        info!("Control server listening on {}", info.url);
+        *self.running.lock().unwrap() = Some(RunningServer {
+            info: info.clone(),
+            shutdown,
+            task,
+        });
+        Ok(info)
+    }
+
+    /// Returns whether a server was running
+    pub fn stop(&self) -> bool {
+        self.signal_stop().is_some()
+    }
+
+    /// Tell the running server to shut down, handing it back so its task can be awaited
+    fn signal_stop(&self) -> Option<RunningServer> {
+        let server = self.running.lock().unwrap().take()?;
+        let _ = server.shutdown.send(true);
+        Some(server)
+    }
+
+    pub fn info(&self) -> Option<ControlServerInfo> {
+        self.running.lock().unwrap().as_ref().map(|server| server.info.clone())
+    }
+}
+
+impl IntoResponse for ErrorResponse {
+    fn into_response(self) -> Response {
+        let status = match self.code {
+            ErrorCode::SessionNotFound | ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::InvalidInput | ErrorCode::NotAGitRepository => StatusCode::BAD_REQUEST,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::GitConflict | ErrorCode::DirtyWorktree => StatusCode::CONFLICT,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            ErrorCode::CliNotFound
+            | ErrorCode::NotAuthenticated
+            | ErrorCode::CliRejectedArguments
+            | ErrorCode::CliFailed
+            | ErrorCode::Network => StatusCode::BAD_GATEWAY,
+            ErrorCode::GitFailed | ErrorCode::Io | ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
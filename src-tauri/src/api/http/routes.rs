@@ -0,0 +1,162 @@
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{middleware, Json, Router};
+use serde::Deserialize;
+use std::fmt::Display;
+use std::sync::Arc;
+use tracing::info;
+
+use super::{auth, events, ServerState};
+use crate::api::commands::start_query;
+use crate::api::models::CreateSessionResponse;
+use crate::core::claude::{Message, QueryOptions, SessionInfo, TurnSummary};
+use crate::core::error::{ErrorCode, ErrorResponse, Result};
+use crate::core::git::{self, GitStatus};
+use crate::core::projects::Project;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateSessionRequest {
+    agent_id: String,
+    project_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SendMessageRequest {
+    prompt: String,
+    options: Option<QueryOptions>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesQuery {
+    since: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GitStatusQuery {
+    directory: Option<String>,
+    project_id: Option<String>,
+}
+
+pub fn router(state: Arc<ServerState>) -> Router {
+    Router::new()
+        .route("/api/sessions", get(list_sessions).post(create_session))
+        .route("/api/sessions/{session_id}", get(get_session))
+        .route("/api/sessions/{session_id}/messages", get(get_messages).post(send_message))
+        .route("/api/sessions/{session_id}/turns", get(get_turns))
+        .route("/api/sessions/{session_id}/abort", post(abort_session))
+        .route("/api/projects", get(list_projects))
+        .route("/api/projects/{project_id}", get(get_project))
+        .route("/api/git/status", get(git_status))
+        .route("/api/events", get(events::follow))
+        .layer(middleware::from_fn_with_state(state.clone(), auth::require_token))
+        .with_state(state)
+}
+
+async fn list_sessions(State(state): State<Arc<ServerState>>) -> Json<Vec<SessionInfo>> {
+    Json(state.context.claude_manager.list_sessions().await)
+}
+
+async fn create_session(
+    State(state): State<Arc<ServerState>>,
+    request: std::result::Result<Json<CreateSessionRequest>, JsonRejection>,
+) -> Result<(StatusCode, Json<CreateSessionResponse>)> {
+    let Json(request) = request.map_err(invalid_request)?;
+    let session_id = state
+        .context
+        .claude_manager
+        .create_session(&request.agent_id, request.project_id.as_deref())
+        .await?;
+    Ok((StatusCode::CREATED, Json(CreateSessionResponse { session_id })))
+}
+
+async fn get_session(
+    State(state): State<Arc<ServerState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<SessionInfo>> {
+    Ok(Json(state.context.claude_manager.session_info(&session_id).await?))
+}
+
+/// Stored messages after `?since=<seq>`, or all of them
+async fn get_messages(
+    State(state): State<Arc<ServerState>>,
+    Path(session_id): Path<String>,
+    query: std::result::Result<Query<MessagesQuery>, QueryRejection>,
+) -> Result<Json<Vec<Message>>> {
+    let Query(query) = query.map_err(invalid_request)?;
+    Ok(Json(state.context.claude_manager.messages_since(&session_id, query.since).await?))
+}
+
+async fn get_turns(
+    State(state): State<Arc<ServerState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Vec<TurnSummary>>> {
+    Ok(Json(state.context.claude_manager.turns(&session_id).await?))
+}
+
+/// Start a query; its messages arrive on the event stream
+async fn send_message(
+    State(state): State<Arc<ServerState>>,
+    Path(session_id): Path<String>,
+    request: std::result::Result<Json<SendMessageRequest>, JsonRejection>,
+) -> Result<StatusCode> {
+    let Json(request) = request.map_err(invalid_request)?;
+    // Fail here rather than on the event stream when the session doesn't exist
+    state.context.claude_manager.session_info(&session_id).await?;
+    info!("Control API send_message - session_id: {}, prompt_length: {}", session_id, request.prompt.len());
+
+    let context = &state.context;
+    start_query(
+        context.app.clone(),
+        context.claude_manager.clone(),
+        context.subscriptions.clone(),
+        session_id,
+        request.prompt,
+        request.options,
+    );
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn abort_session(
+    State(state): State<Arc<ServerState>>,
+    Path(session_id): Path<String>,
+) -> Result<StatusCode> {
+    state.context.claude_manager.abort_session(&session_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_projects(State(state): State<Arc<ServerState>>) -> Json<Vec<Project>> {
+    Json(state.context.project_registry.list())
+}
+
+async fn get_project(
+    State(state): State<Arc<ServerState>>,
+    Path(project_id): Path<String>,
+) -> Result<Json<Project>> {
+    Ok(Json(state.context.project_registry.get(&project_id)?))
+}
+
+/// Status of `?directory=`, `?projectId=` (the project's root), or a directory checked against the project
+async fn git_status(
+    State(state): State<Arc<ServerState>>,
+    query: std::result::Result<Query<GitStatusQuery>, QueryRejection>,
+) -> Result<Json<GitStatus>> {
+    let Query(query) = query.map_err(invalid_request)?;
+    let directory = match (query.project_id, query.directory) {
+        (Some(project_id), directory) => state
+            .context
+            .project_registry
+            .resolve_directory(&project_id, directory.as_deref())?,
+        (None, Some(directory)) => directory,
+        (None, None) => return Err(invalid_request("Either directory or projectId is required")),
+    };
+    Ok(Json(git::check_status(&directory)?))
+}
+
+fn invalid_request(rejection: impl Display) -> ErrorResponse {
+    ErrorResponse::new(ErrorCode::InvalidInput, rejection.to_string())
+}
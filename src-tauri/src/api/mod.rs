@@ -1,2 +1,3 @@
 pub mod commands;
+pub mod http;
 pub mod models;
\ No newline at end of file
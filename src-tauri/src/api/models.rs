@@ -1,3 +1,5 @@
+use crate::core::claude::CliCompatibility;
+use crate::core::git::MergeReport;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -6,6 +8,15 @@ pub struct CreateSessionResponse {
     pub session_id: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeBranchResponse {
+    #[serde(flatten)]
+    pub report: MergeReport,
+    /// Session started to resolve the conflicts, when one was asked for
+    pub resolution_session_id: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ClaudeCliStatus {
@@ -13,4 +24,5 @@ pub struct ClaudeCliStatus {
     pub version: Option<String>,
     pub authenticated: bool,
     pub error: Option<String>,
+    pub compatibility: Option<CliCompatibility>,
 }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// Runs agents from the terminal, streaming session events to stdout as NDJSON
+fn main() -> std::process::ExitCode {
+    forge_app_lib::headless::run()
+}
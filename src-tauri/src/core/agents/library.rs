@@ -0,0 +1,207 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use super::template::{AgentTemplate, TemplateFormat};
+use crate::core::error::{ErrorCode, ForgeError};
+
+/// Templates shipped with the app, one per agent type
+const BUILTIN_TEMPLATES: [&str; 4] = [
+    include_str!("../../../templates/task.toml"),
+    include_str!("../../../templates/explorer.toml"),
+    include_str!("../../../templates/builder.toml"),
+    include_str!("../../../templates/review.toml"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateSource {
+    Builtin,
+    /// From the user's templates directory; replaces a built-in template with the same id
+    User,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateEntry {
+    #[serde(flatten)]
+    pub template: AgentTemplate,
+    pub source: TemplateSource,
+}
+
+/// Built-in templates plus `.toml` and `.json` files in a user directory
+pub struct TemplateLibrary {
+    user_dir: PathBuf,
+}
+
+impl TemplateLibrary {
+    pub fn new(user_dir: PathBuf) -> Self {
+        Self { user_dir }
+    }
+
+    pub fn builtin() -> Vec<AgentTemplate> {
+        BUILTIN_TEMPLATES
+            .iter()
+            .map(|contents| AgentTemplate::parse(contents, TemplateFormat::Toml).expect("built-in template is valid"))
+            .collect()
+    }
+
+    /// Every template by id, user templates replacing built-in ones. Unreadable files are skipped.
+    pub fn list(&self) -> Vec<TemplateEntry> {
+        let mut templates = BTreeMap::new();
+        for template in Self::builtin() {
+            templates.insert(
+                template.id.clone(),
+                TemplateEntry {
+                    template,
+                    source: TemplateSource::Builtin,
+                },
+            );
+        }
+
+        for (path, _) in self.user_files() {
+            match read(&path) {
+                Ok(template) => {
+                    templates.insert(
+                        template.id.clone(),
+                        TemplateEntry {
+                            template,
+                            source: TemplateSource::User,
+                        },
+                    );
+                }
+                Err(e) => warn!("Skipping agent template {}: {}", path.display(), e),
+            }
+        }
+
+        templates.into_values().collect()
+    }
+
+    pub fn get(&self, id: &str) -> Result<TemplateEntry> {
+        self.list()
+            .into_iter()
+            .find(|entry| entry.template.id == id)
+            .ok_or_else(|| {
+                ForgeError::new(ErrorCode::NotFound, format!("No agent template {:?}", id))
+                    .with_details(serde_json::json!({ "templateId": id }))
+                    .into()
+            })
+    }
+
+    /// Copy a template file into the user directory, replacing any template with its id
+    pub fn import(&self, path: &Path) -> Result<AgentTemplate> {
+        let template = read(path)?;
+        let format = TemplateFormat::from_path(path)?;
+        self.remove_user_files(&template.id)?;
+
+        fs::create_dir_all(&self.user_dir)?;
+        let destination = self.user_dir.join(format!("{}.{}", template.id, format.extension()));
+        fs::write(&destination, template.render(format)?)?;
+        info!("Imported agent template {} from {}", template.id, path.display());
+        Ok(template)
+    }
+
+    /// Write a template to `path` in the format its extension names
+    pub fn export(&self, id: &str, path: &Path) -> Result<()> {
+        let format = TemplateFormat::from_path(path)?;
+        let entry = self.get(id)?;
+        fs::write(path, entry.template.render(format)?)?;
+        info!("Exported agent template {} as {} to {}", id, format.label(), path.display());
+        Ok(())
+    }
+
+    /// Delete a user template, bringing back the built-in one it replaced if any
+    pub fn remove(&self, id: &str) -> Result<()> {
+        if self.remove_user_files(id)? == 0 {
+            let message = if Self::builtin().iter().any(|template| template.id == id) {
+                format!("{:?} is a built-in template and can't be removed", id)
+            } else {
+                format!("No user agent template {:?}", id)
+            };
+            return Err(ForgeError::new(ErrorCode::NotFound, message)
+                .with_details(serde_json::json!({ "templateId": id }))
+                .into());
+        }
+        info!("Removed agent template {}", id);
+        Ok(())
+    }
+
+    /// Template files in the user directory with the id each one declares
+    fn user_files(&self) -> Vec<(PathBuf, Option<String>)> {
+        let Ok(entries) = fs::read_dir(&self.user_dir) else {
+            return Vec::new();
+        };
+        let mut files: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && TemplateFormat::from_path(path).is_ok())
+            .map(|path| {
+                let id = read(&path).ok().map(|template| template.id);
+                (path, id)
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    fn remove_user_files(&self, id: &str) -> Result<usize> {
+        let mut removed = 0;
+        for (path, file_id) in self.user_files() {
+            if file_id.as_deref() == Some(id) {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Read a template file in the format its extension names
+pub fn read(path: &Path) -> Result<AgentTemplate> {
+    let format = TemplateFormat::from_path(path)?;
+    let contents = fs::read_to_string(path)?;
+    AgentTemplate::parse(&contents, format)
+        .map_err(|e| ForgeError::new(ErrorCode::InvalidInput, format!("{}: {}", path.display(), e)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn builtin_templates_are_valid() {
+        let templates: Vec<AgentTemplate> = BUILTIN_TEMPLATES
+            .iter()
+            .map(|contents| AgentTemplate::parse(contents, TemplateFormat::Toml).unwrap())
+            .collect();
+        for template in &templates {
+            template.validate().unwrap();
+        }
+
+        let ids: HashSet<&str> = templates.iter().map(|template| template.id.as_str()).collect();
+        assert_eq!(ids.len(), templates.len(), "built-in template ids are unique");
+        let types: HashSet<_> = templates.iter().map(|template| template.agent_type).collect();
+        assert_eq!(types.len(), templates.len(), "one built-in template per agent type");
+    }
+
+    #[test]
+    fn templates_round_trip_through_both_formats() {
+        for template in TemplateLibrary::builtin() {
+            let expected = serde_json::to_value(&template).unwrap();
+            for format in [TemplateFormat::Toml, TemplateFormat::Json] {
+                let rendered = template.render(format).unwrap();
+                let parsed = AgentTemplate::parse(&rendered, format).unwrap();
+                assert_eq!(serde_json::to_value(&parsed).unwrap(), expected, "{} as {}", template.id, format.label());
+            }
+
+            // Converting from one format to the other keeps everything too
+            let json = template.render(TemplateFormat::Json).unwrap();
+            let toml = AgentTemplate::parse(&json, TemplateFormat::Json).unwrap().render(TemplateFormat::Toml).unwrap();
+            let parsed = AgentTemplate::parse(&toml, TemplateFormat::Toml).unwrap();
+            assert_eq!(serde_json::to_value(&parsed).unwrap(), expected, "{} from JSON to TOML", template.id);
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod library;
+mod template;
+
+pub use library::{read as read_template, TemplateEntry, TemplateLibrary, TemplateSource};
+pub use template::{AgentTemplate, AgentType, PermissionMode, TemplateFormat};
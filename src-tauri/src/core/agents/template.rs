@@ -0,0 +1,160 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::core::claude::AgentConfig;
+use crate::core::error::{ErrorCode, ForgeError};
+
+/// The kinds of agent the app offers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentType {
+    /// Single-purpose work such as a bug fix or small feature
+    Task,
+    /// Reads and explains a codebase without changing it
+    Explorer,
+    /// Long-running scaffolding and feature work
+    Builder,
+    /// Code review and suggestions
+    Review,
+}
+
+/// How the CLI asks before using tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionMode {
+    Default,
+    AcceptEdits,
+    BypassPermissions,
+    /// Read-only: the agent can look around and plan but not change anything
+    Plan,
+}
+
+impl PermissionMode {
+    /// Value of the CLI's `--permission-mode`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::AcceptEdits => "acceptEdits",
+            Self::BypassPermissions => "bypassPermissions",
+            Self::Plan => "plan",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateFormat {
+    Toml,
+    Json,
+}
+
+impl TemplateFormat {
+    /// Format of a template file, by its extension
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Ok(Self::Toml),
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Ok(Self::Json),
+            _ => Err(ForgeError::new(
+                ErrorCode::InvalidInput,
+                format!("{} is not a .toml or .json template", path.display()),
+            )
+            .into()),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TemplateFormat::Toml => "toml",
+            TemplateFormat::Json => "json",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TemplateFormat::Toml => "TOML",
+            TemplateFormat::Json => "JSON",
+        }
+    }
+}
+
+/// Defaults for new agents. Stored as TOML or JSON with the camelCase keys below;
+/// see templates/README.md for the format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentTemplate {
+    /// Also the file name in the user's templates directory
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub agent_type: AgentType,
+    pub description: Option<String>,
+    /// Added to the CLI's own system prompt
+    pub system_prompt: Option<String>,
+    pub model: Option<String>,
+    pub max_turns: Option<i32>,
+    pub allowed_tools: Option<Vec<String>>,
+    pub disallowed_tools: Option<Vec<String>>,
+    pub permission_mode: Option<PermissionMode>,
+}
+
+impl AgentTemplate {
+    pub fn parse(contents: &str, format: TemplateFormat) -> Result<Self> {
+        let template: Self = match format {
+            TemplateFormat::Toml => toml::from_str(contents)
+                .map_err(|e| ForgeError::new(ErrorCode::InvalidInput, format!("Invalid template: {}", e)))?,
+            TemplateFormat::Json => serde_json::from_str(contents)
+                .map_err(|e| ForgeError::new(ErrorCode::InvalidInput, format!("Invalid template: {}", e)))?,
+        };
+        template.validate()?;
+        Ok(template)
+    }
+
+    pub fn render(&self, format: TemplateFormat) -> Result<String> {
+        match format {
+            TemplateFormat::Toml => Ok(toml::to_string_pretty(self)?),
+            TemplateFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+        }
+    }
+
+    /// Ids name files, so keep them to lowercase letters, digits, `-` and `_`
+    pub fn validate(&self) -> Result<()> {
+        let valid_id = !self.id.is_empty()
+            && self
+                .id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if !valid_id {
+            return Err(ForgeError::new(
+                ErrorCode::InvalidInput,
+                format!("Invalid template id {:?}: use lowercase letters, digits, '-' and '_'", self.id),
+            )
+            .into());
+        }
+        if self.name.trim().is_empty() {
+            return Err(ForgeError::new(ErrorCode::InvalidInput, "Template name is empty").into());
+        }
+        Ok(())
+    }
+
+    /// A new agent with this template's settings
+    pub fn agent_config(&self, agent_id: &str, name: Option<String>) -> AgentConfig {
+        AgentConfig {
+            id: agent_id.to_string(),
+            name: name.unwrap_or_else(|| self.name.clone()),
+            agent_type: self.agent_type,
+            template: Some(self.id.clone()),
+            system_prompt: self.system_prompt.clone(),
+            working_directory: None,
+            branch: None,
+            model: self.model.clone(),
+            max_tokens: None,
+            temperature: None,
+            max_turns: self.max_turns,
+            allowed_tools: self.allowed_tools.clone(),
+            disallowed_tools: self.disallowed_tools.clone(),
+            permission_mode: self.permission_mode,
+            retry_policy: None,
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Files a turn touched and the commands it ran, taken from its tool calls
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnActivity {
+    pub files_read: Vec<String>,
+    /// Created or overwritten with `Write`
+    pub files_written: Vec<String>,
+    /// Changed in place with `Edit`, `MultiEdit` or `NotebookEdit`
+    pub files_edited: Vec<String>,
+    pub commands: Vec<BashCommand>,
+}
+
+impl TurnActivity {
+    /// Files the turn wrote or edited
+    pub fn files_changed(&self) -> impl Iterator<Item = &str> {
+        self.files_written.iter().chain(&self.files_edited).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BashCommand {
+    pub command: String,
+    pub description: Option<String>,
+    /// The tool reported an error, e.g. a non-zero exit status
+    pub failed: bool,
+}
+
+enum ToolUse {
+    Read(String),
+    Write(String),
+    Edit(String),
+    Bash { command: String, description: Option<String> },
+}
+
+/// Builds a turn's activity as its tool calls and their results stream in, across retries
+#[derive(Default)]
+pub(super) struct ActivityTracker {
+    uses: Vec<(String, ToolUse)>,
+    failed: HashSet<String>,
+}
+
+impl ActivityTracker {
+    pub fn record_tool_use(&mut self, id: &str, name: &str, input: &serde_json::Value) {
+        let text = |key: &str| input.get(key).and_then(|value| value.as_str()).map(str::to_string);
+        let tool_use = match name {
+            "Read" => text("file_path").map(ToolUse::Read),
+            "NotebookRead" => text("notebook_path").map(ToolUse::Read),
+            "Write" => text("file_path").map(ToolUse::Write),
+            "Edit" | "MultiEdit" => text("file_path").map(ToolUse::Edit),
+            "NotebookEdit" => text("notebook_path").map(ToolUse::Edit),
+            "Bash" => text("command").map(|command| ToolUse::Bash {
+                command,
+                description: text("description"),
+            }),
+            _ => None,
+        };
+        if let Some(tool_use) = tool_use {
+            self.uses.push((id.to_string(), tool_use));
+        }
+    }
+
+    pub fn record_failure(&mut self, tool_use_id: &str) {
+        self.failed.insert(tool_use_id.to_string());
+    }
+
+    /// Each file once, in the order it was first touched. Failed reads and edits changed nothing
+    /// and are left out; failed commands are kept since they may have done part of their work.
+    pub fn finish(&self) -> TurnActivity {
+        let mut activity = TurnActivity::default();
+        for (id, tool_use) in &self.uses {
+            let failed = self.failed.contains(id);
+            let (files, path) = match tool_use {
+                ToolUse::Bash { command, description } => {
+                    activity.commands.push(BashCommand {
+                        command: command.clone(),
+                        description: description.clone(),
+                        failed,
+                    });
+                    continue;
+                }
+                _ if failed => continue,
+                ToolUse::Read(path) => (&mut activity.files_read, path),
+                ToolUse::Write(path) => (&mut activity.files_written, path),
+                ToolUse::Edit(path) => (&mut activity.files_edited, path),
+            };
+            if !files.contains(path) {
+                files.push(path.clone());
+            }
+        }
+        activity
+    }
+}
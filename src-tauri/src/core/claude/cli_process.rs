@@ -1,16 +1,49 @@
 use anyhow::{anyhow, Result};
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json;
+use std::collections::VecDeque;
+use std::path::PathBuf;
 use std::process::Stdio;
+use std::sync::{Arc, Mutex};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::{Child, Command};
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
 use tokio::task::JoinHandle;
-use tracing::{debug, error, info, warn};
+use tokio::time::{timeout, Duration};
+use tracing::{debug, error, info, warn, Instrument};
 
+use super::compat;
+use super::retry::INTERRUPTED_SUBTYPE;
+
+/// Number of trailing stderr lines kept per process
+const MAX_STDERR_LINES: usize = 200;
+/// Runs this executable instead of `claude` from the PATH, e.g. a stand-in for benchmarks
+const CLI_BINARY_ENV: &str = "FORGE_CLAUDE_BIN";
+/// Denied in plan mode so the agent can look around but not change anything
+const PLAN_DENIED_TOOLS: [&str; 5] = ["Edit", "MultiEdit", "Write", "NotebookEdit", "Bash"];
+
+/// Message types the CLI emits that we parse; anything else becomes `ClaudeCliMessage::Unknown`
+const KNOWN_MESSAGE_TYPES: [&str; 4] = ["system", "assistant", "user", "result"];
+/// Content block types we parse; anything else becomes `ContentBlock::Unknown`
+const KNOWN_BLOCK_TYPES: [&str; 11] = [
+    "text",
+    "tool_use",
+    "tool_result",
+    "image",
+    "thinking",
+    "server_tool_use",
+    "web_search_tool_result",
+    "code_execution_tool_result",
+    "mcp_tool_use",
+    "mcp_tool_result",
+    "container_upload",
+];
+
+// `remote = "Self"` derives inherent (de)serialize functions, wrapped below by the trait impls
+// that route unrecognized `type`s to `Unknown`
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "lowercase")]
+#[serde(remote = "Self", tag = "type", rename_all = "lowercase")]
 pub enum ClaudeCliMessage {
     #[serde(rename = "system")]
     System {
@@ -39,6 +72,7 @@ pub enum ClaudeCliMessage {
     #[serde(rename = "result")]
     Result {
         subtype: String,
+        /// Taken from the subtype when missing
         is_error: bool,
         #[serde(default)]
         result: Option<String>,
@@ -48,7 +82,47 @@ pub enum ClaudeCliMessage {
         session_id: Option<String>,
         #[serde(default)]
         duration_ms: Option<u64>,
+        #[serde(default)]
+        duration_api_ms: Option<u64>,
+        #[serde(default)]
+        num_turns: Option<u32>,
+        #[serde(default, alias = "cost_usd")]
+        total_cost_usd: Option<f64>,
+        #[serde(default)]
+        usage: Option<Usage>,
+        /// Tail of the process's stderr, attached by us to failed results
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stderr: Option<String>,
     },
+    /// Any message of a type we don't recognize, kept as raw JSON
+    #[serde(untagged, skip_deserializing)]
+    Unknown(serde_json::Value),
+}
+
+impl<'de> Deserialize<'de> for ClaudeCliMessage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut raw = serde_json::Value::deserialize(deserializer)?;
+        let kind = raw.get("type").and_then(|kind| kind.as_str()).unwrap_or_default().to_string();
+        if !KNOWN_MESSAGE_TYPES.contains(&kind.as_str()) {
+            return Ok(ClaudeCliMessage::Unknown(raw));
+        }
+        if let Some(fields) = raw.as_object_mut().filter(|_| kind == "result") {
+            if !fields.contains_key("is_error") {
+                let failed = fields
+                    .get("subtype")
+                    .and_then(|subtype| subtype.as_str())
+                    .is_some_and(|subtype| subtype != "success");
+                fields.insert("is_error".to_string(), failed.into());
+            }
+        }
+        ClaudeCliMessage::deserialize(raw).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for ClaudeCliMessage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ClaudeCliMessage::serialize(self, serializer)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,6 +143,7 @@ pub struct AssistantMessage {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Usage {
+    #[serde(default)]
     pub input_tokens: u32,
     #[serde(default)]
     pub output_tokens: u32,
@@ -81,7 +156,7 @@ pub struct Usage {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "snake_case")]
+#[serde(remote = "Self", tag = "type", rename_all = "snake_case")]
 pub enum ContentBlock {
     Text { 
         text: String 
@@ -95,7 +170,8 @@ pub enum ContentBlock {
     #[serde(rename = "tool_result")]
     ToolResult {
         tool_use_id: String,
-        #[serde(default)]
+        /// The text of the result, which may also come as a list of text blocks
+        #[serde(default, deserialize_with = "tool_result_text")]
         content: Option<String>,
         #[serde(default)]
         is_error: bool,
@@ -138,6 +214,42 @@ pub enum ContentBlock {
         container_id: String,
         files: Vec<String>,
     },
+    /// Any block of a type we don't recognize, kept as raw JSON
+    #[serde(untagged, skip_deserializing)]
+    Unknown(serde_json::Value),
+}
+
+impl<'de> Deserialize<'de> for ContentBlock {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = serde_json::Value::deserialize(deserializer)?;
+        let kind = raw.get("type").and_then(|kind| kind.as_str()).unwrap_or_default();
+        if !KNOWN_BLOCK_TYPES.contains(&kind) {
+            return Ok(ContentBlock::Unknown(raw));
+        }
+        ContentBlock::deserialize(raw).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for ContentBlock {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ContentBlock::serialize(self, serializer)
+    }
+}
+
+/// Join the text blocks of a tool result given as a list; other blocks, like images, are dropped
+fn tool_result_text<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(text) => Some(text),
+        serde_json::Value::Array(blocks) => {
+            let texts: Vec<&str> = blocks
+                .iter()
+                .filter(|block| block.get("type").and_then(|kind| kind.as_str()) == Some("text"))
+                .filter_map(|block| block.get("text").and_then(|text| text.as_str()))
+                .collect();
+            (!texts.is_empty()).then(|| texts.join("\n"))
+        }
+        _ => None,
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -154,6 +266,29 @@ pub struct ClaudeCliProcess {
     abort_sender: oneshot::Sender<()>,
 }
 
+/// Bounded buffer of the most recent stderr lines of a process
+#[derive(Clone, Default)]
+struct StderrBuffer(Arc<Mutex<VecDeque<String>>>);
+
+impl StderrBuffer {
+    fn push(&self, line: String) {
+        let mut lines = self.0.lock().unwrap();
+        if lines.len() == MAX_STDERR_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    fn contents(&self) -> Option<String> {
+        let lines = self.0.lock().unwrap();
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.iter().cloned().collect::<Vec<_>>().join("\n"))
+        }
+    }
+}
+
 impl ClaudeCliProcess {
     pub async fn spawn(
         prompt: &str,
@@ -166,18 +301,39 @@ impl ClaudeCliProcess {
         debug!("Session ID: {:?}", session_id);
         debug!("Options: {:?}", options);
         
-        let mut cmd = Command::new("claude");
+        let program = std::env::var_os(CLI_BINARY_ENV).unwrap_or_else(|| "claude".into());
+        let mut cmd = Command::new(program);
         
         // Always use streaming JSON format for machine-readable output
-        // Skip permission checks for seamless tool usage
         cmd.arg("--print")
            .arg("--output-format").arg("stream-json")
-           .arg("--verbose")
-           .arg("--dangerously-skip-permissions");
+           .arg("--verbose");
+
+        // Under --print nothing can answer a permission prompt, so tool calls that would ask get
+        // denied. Permission checks are skipped instead, and plan mode is kept read-only by
+        // denying the tools that change things.
+        cmd.arg("--dangerously-skip-permissions");
+        let mut disallowed_tools = options.disallowed_tools.clone().unwrap_or_default();
+        match options.permission_mode.as_deref() {
+            None | Some("bypassPermissions") => {}
+            Some("plan") => {
+                for tool in PLAN_DENIED_TOOLS {
+                    if !disallowed_tools.iter().any(|denied| denied == tool) {
+                        disallowed_tools.push(tool.to_string());
+                    }
+                }
+            }
+            Some(mode) => warn!("Permission mode {} needs prompts that can't be answered here, skipping permission checks", mode),
+        }
         
         // Add session ID if provided
         if let Some(sid) = session_id {
             cmd.arg("--resume").arg(sid);
+            
+            // Continue under a new CLI session id, leaving the original untouched
+            if options.fork_session {
+                cmd.arg("--fork-session");
+            }
         }
         
         // Add model if specified
@@ -185,6 +341,17 @@ impl ClaudeCliProcess {
             cmd.arg("--model").arg(model);
         }
         
+        if let Some(system_prompt) = &options.system_prompt {
+            cmd.arg("--system-prompt").arg(system_prompt);
+        }
+        if let Some(append) = &options.append_system_prompt {
+            cmd.arg("--append-system-prompt").arg(append);
+        }
+
+        if let Some(max_turns) = options.max_turns {
+            cmd.arg("--max-turns").arg(max_turns.to_string());
+        }
+        
         // Add allowed tools
         if let Some(allowed) = &options.allowed_tools {
             if !allowed.is_empty() {
@@ -193,10 +360,13 @@ impl ClaudeCliProcess {
         }
         
         // Add disallowed tools
-        if let Some(disallowed) = &options.disallowed_tools {
-            if !disallowed.is_empty() {
-                cmd.arg("--disallowedTools").arg(disallowed.join(","));
-            }
+        if !disallowed_tools.is_empty() {
+            cmd.arg("--disallowedTools").arg(disallowed_tools.join(","));
+        }
+        
+        // Add MCP servers
+        if let Some(mcp_config) = &options.mcp_config {
+            cmd.arg("--mcp-config").arg(mcp_config);
         }
         
         // Add working directory
@@ -204,8 +374,9 @@ impl ClaudeCliProcess {
             cmd.current_dir(cwd);
         }
         
-        // Add the prompt as the last argument
-        cmd.arg(prompt);
+        // Add the prompt as the last argument, after `--` so variadic
+        // options like --allowedTools and --mcp-config don't consume it
+        cmd.arg("--").arg(prompt);
         
         // Configure process pipes
         cmd.stdin(Stdio::null())
@@ -222,6 +393,8 @@ impl ClaudeCliProcess {
         })?;
         
         info!("Claude CLI process spawned with PID: {:?}", child.id());
+        tracing::Span::current().record("pid", child.id());
+        let process_span = crate::span!("cli_process", pid = child.id());
         
         // Take stdout for reading
         let stdout = child.stdout.take()
@@ -231,15 +404,19 @@ impl ClaudeCliProcess {
         let stderr = child.stderr.take()
             .ok_or_else(|| anyhow!("Failed to capture stderr"))?;
         
-        // Spawn task to read stderr
-        tokio::spawn(async move {
+        // Spawn task to read stderr, keeping its tail for diagnostics
+        let stderr_buffer = StderrBuffer::default();
+        let stderr_writer = stderr_buffer.clone();
+        let mut stderr_handle = tokio::spawn(async move {
             let reader = BufReader::new(stderr);
             let mut lines = reader.lines();
             
             while let Ok(Some(line)) = lines.next_line().await {
                 error!("Claude CLI stderr: {}", line);
+                stderr_writer.push(line);
             }
-        });
+        }.instrument(process_span.clone()));
+        let stderr_reader = stderr_buffer;
         
         // Create abort channel
         let (abort_sender, mut abort_receiver) = oneshot::channel();
@@ -260,16 +437,33 @@ impl ClaudeCliProcess {
                             Ok(Some(line)) => {
                                 line_count += 1;
                                 debug!("Received line {}: {}", line_count, &line);
+                                compat::record_line();
                                 
                                 // Parse JSON line
                                 match serde_json::from_str::<ClaudeCliMessage>(&line) {
-                                    Ok(msg) => {
-                                        let msg_type = match &msg {
+                                    Ok(mut msg) => {
+                                        let msg_type = match &mut msg {
                                             ClaudeCliMessage::System { .. } => "system",
-                                            ClaudeCliMessage::Assistant { .. } => "assistant",
+                                            ClaudeCliMessage::Assistant { message, .. } => {
+                                                for block in &message.content {
+                                                    if let ContentBlock::Unknown(raw) = block {
+                                                        warn!("Unrecognized content block: {}", raw);
+                                                        compat::record_unknown_block(raw);
+                                                    }
+                                                }
+                                                "assistant"
+                                            }
                                             ClaudeCliMessage::User { .. } => "user",
-                                            ClaudeCliMessage::Result { .. } => {
+                                            ClaudeCliMessage::Unknown(raw) => {
+                                                warn!("Unrecognized Claude CLI message: {}", raw);
+                                                compat::record_unknown_message(raw);
+                                                "unknown"
+                                            }
+                                            ClaudeCliMessage::Result { is_error, stderr, .. } => {
                                                 received_result = true;
+                                                if *is_error && stderr.is_none() {
+                                                    *stderr = stderr_reader.contents();
+                                                }
                                                 "result"
                                             }
                                         };
@@ -282,6 +476,7 @@ impl ClaudeCliProcess {
                                     }
                                     Err(e) => {
                                         error!("Failed to parse Claude CLI output: {} - Line: {}", e, line);
+                                        compat::record_parse_failure(&line);
                                     }
                                 }
                             }
@@ -291,13 +486,23 @@ impl ClaudeCliProcess {
                                 // Send a synthetic result message if we haven't received one
                                 if !received_result {
                                     warn!("Process ended without sending a result message - sending synthetic result");
+                                    
+                                    // Let the stderr reader drain so the result carries the cause
+                                    let _ = timeout(Duration::from_secs(2), &mut stderr_handle).await;
+                                    
+
                                     let synthetic_result = ClaudeCliMessage::Result {
-                                        subtype: "interrupted".to_string(),
+                                        subtype: INTERRUPTED_SUBTYPE.to_string(),
                                         is_error: true,
                                         result: None,
                                         error: Some("Process ended without completion message".to_string()),
                                         session_id: None,
                                         duration_ms: None,
+                                        duration_api_ms: None,
+                                        num_turns: None,
+                                        total_cost_usd: None,
+                                        usage: None,
+                                        stderr: stderr_reader.contents(),
                                     };
                                     
                                     if message_sender.send(synthetic_result).await.is_err() {
@@ -318,6 +523,11 @@ impl ClaudeCliProcess {
                                     error: Some(format!("Error reading output: {}", e)),
                                     session_id: None,
                                     duration_ms: None,
+                                    duration_api_ms: None,
+                                    num_turns: None,
+                                    total_cost_usd: None,
+                                    usage: None,
+                                    stderr: stderr_reader.contents(),
                                 };
                                 
                                 let _ = message_sender.send(error_result).await;
@@ -334,7 +544,7 @@ impl ClaudeCliProcess {
             
             info!("Output reader task completed");
             Ok(())
-        });
+        }.instrument(process_span));
         
         Ok(Self {
             child,
@@ -342,7 +552,11 @@ impl ClaudeCliProcess {
             abort_sender,
         })
     }
-    
+
+    pub fn pid(&self) -> Option<u32> {
+        self.child.id()
+    }
+
     pub async fn abort(mut self) -> Result<()> {
         info!("Aborting Claude CLI process");
         
@@ -387,5 +601,13 @@ pub struct ClaudeCliOptions {
     pub model: Option<String>,
     pub allowed_tools: Option<Vec<String>>,
     pub disallowed_tools: Option<Vec<String>>,
+    /// Replaces the CLI's system prompt
+    pub system_prompt: Option<String>,
+    pub append_system_prompt: Option<String>,
+    pub max_turns: Option<i32>,
+    /// Permission mode to run in; permission checks are skipped when not set
+    pub permission_mode: Option<String>,
     pub working_directory: Option<String>,
+    pub mcp_config: Option<PathBuf>,
+    pub fork_session: bool,
 }
\ No newline at end of file
@@ -0,0 +1,338 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use super::manager::{describe_tool_use, ToolCall};
+use crate::core::error::{ErrorCode, ForgeError};
+
+/// Longest first prompt shown in a session listing
+const MAX_PREVIEW_LEN: usize = 200;
+
+/// A conversation the CLI saved to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliSessionInfo {
+    pub cli_session_id: String,
+    pub working_directory: Option<String>,
+    pub first_prompt: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// User prompts and assistant replies, not counting tool results
+    pub message_count: usize,
+    pub size_bytes: u64,
+    pub path: PathBuf,
+}
+
+/// A message recovered from a CLI transcript
+#[derive(Debug, Clone)]
+pub struct TranscriptEntry {
+    pub role: String,
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+    pub tool: Option<ToolCall>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TranscriptLine {
+    #[serde(rename = "type")]
+    kind: String,
+    is_meta: bool,
+    is_sidechain: bool,
+    cwd: Option<String>,
+    timestamp: Option<DateTime<Utc>>,
+    message: Option<Value>,
+}
+
+impl TranscriptLine {
+    /// Messages this line contributes to the conversation
+    fn entries(&self, timestamp: DateTime<Utc>) -> Vec<TranscriptEntry> {
+        if self.is_meta || self.is_sidechain {
+            return Vec::new();
+        }
+        let Some(content) = self.message.as_ref().and_then(|message| message.get("content")) else {
+            return Vec::new();
+        };
+
+        let entry = |role: &str, content: String, tool: Option<ToolCall>| TranscriptEntry {
+            role: role.to_string(),
+            content,
+            timestamp,
+            tool,
+        };
+
+        match (self.kind.as_str(), content) {
+            ("user", Value::String(text)) => vec![entry("user", text.clone(), None)],
+            ("user" | "assistant", Value::Array(blocks)) => blocks
+                .iter()
+                .filter_map(|block| match block.get("type").and_then(Value::as_str) {
+                    Some("text") => block
+                        .get("text")
+                        .and_then(Value::as_str)
+                        .filter(|text| !text.trim().is_empty())
+                        .map(|text| entry(&self.kind, text.to_string(), None)),
+                    Some("tool_use") if self.kind == "assistant" => {
+                        let tool: ToolCall = serde_json::from_value(block.clone()).ok()?;
+                        Some(entry("system", describe_tool_use(&tool.name, &tool.input), Some(tool)))
+                    }
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn read_lines(path: &Path) -> Result<impl Iterator<Item = TranscriptLine>> {
+    let file = File::open(path)?;
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(|line| line.ok())
+        .filter_map(|line| serde_json::from_str::<TranscriptLine>(&line).ok()))
+}
+
+/// The CLI's config directory, `~/.claude` unless overridden
+pub fn config_dir() -> Option<PathBuf> {
+    if let Ok(dir) = env::var("CLAUDE_CONFIG_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    env::var("HOME")
+        .or_else(|_| env::var("USERPROFILE"))
+        .ok()
+        .map(|home| Path::new(&home).join(".claude"))
+}
+
+/// Directory where the CLI keeps transcripts of sessions started in `cwd`.
+/// The CLI names it after the absolute path with every non-alphanumeric character replaced by `-`.
+pub fn project_dir(cwd: &str) -> Option<PathBuf> {
+    let cwd = fs::canonicalize(cwd).unwrap_or_else(|_| PathBuf::from(cwd));
+    let encoded: String = cwd
+        .to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    config_dir().map(|dir| dir.join("projects").join(encoded))
+}
+
+/// Make a CLI session started in `from_cwd` resumable from `to_cwd`
+pub fn copy_session(cli_session_id: &str, from_cwd: &str, to_cwd: &str) -> Result<()> {
+    let file_name = transcript_file_name(cli_session_id)?;
+    let source = project_dir(from_cwd)
+        .map(|dir| dir.join(&file_name))
+        .ok_or_else(|| anyhow!("Cannot locate the Claude CLI config directory"))?;
+    let target_dir = project_dir(to_cwd)
+        .ok_or_else(|| anyhow!("Cannot locate the Claude CLI config directory"))?;
+
+    if !source.exists() {
+        return Err(ForgeError::new(
+            ErrorCode::NotFound,
+            format!("CLI transcript not found: {}", source.display()),
+        )
+        .into());
+    }
+
+    fs::create_dir_all(&target_dir)?;
+    fs::copy(&source, target_dir.join(&file_name))?;
+
+    info!("Copied CLI session {} from {} to {}", cli_session_id, from_cwd, to_cwd);
+    Ok(())
+}
+
+/// List the sessions saved by the CLI, newest first, optionally only those started in `cwd`
+pub fn list_sessions(cwd: Option<&str>) -> Result<Vec<CliSessionInfo>> {
+    let project_dirs = match cwd {
+        Some(cwd) => project_dir(cwd).into_iter().collect::<Vec<_>>(),
+        None => {
+            let projects = config_dir()
+                .ok_or_else(|| anyhow!("Cannot locate the Claude CLI config directory"))?
+                .join("projects");
+            match fs::read_dir(&projects) {
+                Ok(entries) => entries.filter_map(|entry| Some(entry.ok()?.path())).collect(),
+                Err(_) => Vec::new(),
+            }
+        }
+    };
+
+    let mut sessions = Vec::new();
+    for dir in project_dirs {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("jsonl") {
+                continue;
+            }
+            match describe_session(&path) {
+                Ok(Some(info)) => sessions.push(info),
+                Ok(None) => {}
+                Err(e) => debug!("Skipping CLI transcript {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    sessions.sort_by_key(|session| std::cmp::Reverse(session.updated_at));
+    info!("Found {} CLI sessions", sessions.len());
+    Ok(sessions)
+}
+
+/// Find a CLI session by id in any project directory
+pub fn find_session(cli_session_id: &str) -> Result<CliSessionInfo> {
+    let file_name = transcript_file_name(cli_session_id)?;
+    let projects = config_dir()
+        .ok_or_else(|| anyhow!("Cannot locate the Claude CLI config directory"))?
+        .join("projects");
+
+    fs::read_dir(&projects)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path().join(&file_name))
+        .find(|path| path.is_file())
+        .and_then(|path| describe_session(&path).transpose())
+        .unwrap_or_else(|| {
+            Err(ForgeError::new(ErrorCode::NotFound, format!("CLI session not found: {}", cli_session_id)).into())
+        })
+}
+
+/// Name of the transcript file of a CLI session. The CLI's ids are UUIDs; anything else is
+/// refused so an id can't point outside the transcript directories.
+fn transcript_file_name(cli_session_id: &str) -> Result<String> {
+    let id = Uuid::parse_str(cli_session_id).map_err(|_| {
+        ForgeError::new(ErrorCode::InvalidInput, format!("Invalid CLI session id: {}", cli_session_id))
+            .with_details(serde_json::json!({ "cliSessionId": cli_session_id }))
+    })?;
+    Ok(format!("{}.jsonl", id.hyphenated()))
+}
+
+/// Summarize a transcript file; `None` if it holds no conversation
+fn describe_session(path: &Path) -> Result<Option<CliSessionInfo>> {
+    let Some(cli_session_id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+        return Ok(None);
+    };
+    let size_bytes = fs::metadata(path)?.len();
+
+    let mut info = CliSessionInfo {
+        cli_session_id: cli_session_id.to_string(),
+        working_directory: None,
+        first_prompt: None,
+        started_at: None,
+        updated_at: None,
+        message_count: 0,
+        size_bytes,
+        path: path.to_path_buf(),
+    };
+
+    for line in read_lines(path)? {
+        if info.working_directory.is_none() {
+            info.working_directory = line.cwd.clone();
+        }
+        if let Some(timestamp) = line.timestamp {
+            info.started_at.get_or_insert(timestamp);
+            info.updated_at = Some(timestamp);
+        }
+        for entry in line.entries(Utc::now()) {
+            if entry.tool.is_some() {
+                continue;
+            }
+            if entry.role == "user" && info.first_prompt.is_none() {
+                info.first_prompt = Some(preview(&entry.content));
+            }
+            info.message_count += 1;
+        }
+    }
+
+    Ok((info.message_count > 0).then_some(info))
+}
+
+/// Messages of a saved CLI session, in order
+pub fn read_transcript(path: &Path) -> Result<Vec<TranscriptEntry>> {
+    let mut entries = Vec::new();
+    let mut last_timestamp = Utc::now();
+    for line in read_lines(path)? {
+        let timestamp = line.timestamp.unwrap_or(last_timestamp);
+        last_timestamp = timestamp;
+        entries.extend(line.entries(timestamp));
+    }
+    Ok(entries)
+}
+
+/// Whether the last prompt saved in a CLI session is `prompt`, i.e. the CLI recorded it
+/// before the run failed
+pub fn ends_with_prompt(cli_session_id: &str, prompt: &str) -> bool {
+    let Ok(session) = find_session(cli_session_id) else {
+        return false;
+    };
+    read_transcript(&session.path)
+        .ok()
+        .and_then(|entries| entries.into_iter().rev().find(|entry| entry.role == "user" && entry.tool.is_none()))
+        .is_some_and(|entry| entry.content.trim() == prompt.trim())
+}
+
+fn preview(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(MAX_PREVIEW_LEN) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(json: &str) -> TranscriptLine {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn long_multibyte_bash_commands_are_cut_on_characters() {
+        // "é" is two bytes, so byte 50 falls inside a character
+        let command = format!("echo {}", "é".repeat(60));
+        let json = serde_json::json!({
+            "type": "assistant",
+            "message": {"content": [{"type": "tool_use", "id": "t1", "name": "Bash", "input": {"command": command}}]}
+        });
+        let entries = line(&json.to_string()).entries(Utc::now());
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].role, "system");
+        let preview: String = command.chars().take(50).collect();
+        assert_eq!(entries[0].content, format!("💻 Running: {}...", preview));
+    }
+
+    #[test]
+    fn user_prompts_and_assistant_text_become_entries() {
+        let prompt = line(r#"{"type":"user","message":{"content":"修正して"}}"#);
+        assert_eq!(prompt.entries(Utc::now())[0].content, "修正して");
+
+        let reply = line(r#"{"type":"assistant","message":{"content":[{"type":"text","text":"  "},{"type":"text","text":"Done"}]}}"#);
+        let entries = reply.entries(Utc::now());
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].role, "assistant");
+    }
+
+    #[test]
+    fn meta_and_sidechain_lines_are_skipped() {
+        assert!(line(r#"{"type":"user","isMeta":true,"message":{"content":"x"}}"#).entries(Utc::now()).is_empty());
+        assert!(line(r#"{"type":"user","isSidechain":true,"message":{"content":"x"}}"#).entries(Utc::now()).is_empty());
+        assert!(line(r#"{"type":"summary"}"#).entries(Utc::now()).is_empty());
+    }
+
+    #[test]
+    fn session_ids_must_be_uuids() {
+        assert!(transcript_file_name("../../etc/passwd").is_err());
+        assert!(transcript_file_name("").is_err());
+        assert_eq!(
+            transcript_file_name("0F8FAD5B-D9CB-469F-A165-70867728950E").unwrap(),
+            "0f8fad5b-d9cb-469f-a165-70867728950e.jsonl"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+/// Longest raw line kept as a sample of unparsed output
+const MAX_SAMPLE_LEN: usize = 2_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompatibilityStatus {
+    Supported,
+    Untested,
+    Unsupported,
+}
+
+struct CompatEntry {
+    /// Inclusive lower bound
+    from: (u32, u32, u32),
+    /// Exclusive upper bound
+    until: Option<(u32, u32, u32)>,
+    status: CompatibilityStatus,
+    notes: &'static str,
+}
+
+/// Claude CLI versions we know how to talk to. Versions newer than every entry
+/// are untested: unknown message types and content blocks are kept raw.
+const COMPATIBILITY_TABLE: &[CompatEntry] = &[
+    CompatEntry {
+        from: (0, 0, 0),
+        until: Some((1, 0, 0)),
+        status: CompatibilityStatus::Unsupported,
+        notes: "Pre-1.0 releases emit an older stream-json format",
+    },
+    CompatEntry {
+        from: (1, 0, 0),
+        until: Some((3, 0, 0)),
+        status: CompatibilityStatus::Supported,
+        notes: "stream-json output with --verbose is fully supported",
+    },
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliCompatibility {
+    pub version: String,
+    pub status: CompatibilityStatus,
+    pub notes: String,
+}
+
+impl CliCompatibility {
+    /// Look up a version string as printed by `claude --version`, e.g. "1.0.51 (Claude Code)"
+    pub fn check(version: &str) -> Self {
+        let entry = parse_version(version).and_then(|parsed| {
+            COMPATIBILITY_TABLE.iter().find(|entry| {
+                parsed >= entry.from && entry.until.is_none_or(|until| parsed < until)
+            })
+        });
+
+        let (status, notes) = match entry {
+            Some(entry) => (entry.status, entry.notes),
+            None => (
+                CompatibilityStatus::Untested,
+                "This version has not been tested with Forge; unrecognized output will be ignored",
+            ),
+        };
+
+        Self {
+            version: version.to_string(),
+            status,
+            notes: notes.to_string(),
+        }
+    }
+}
+
+fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let number = version.split_whitespace().next()?;
+    let mut parts = number.trim_start_matches('v').split('.').map(|p| p.parse::<u32>().ok());
+    Some((parts.next()??, parts.next().flatten().unwrap_or(0), parts.next().flatten().unwrap_or(0)))
+}
+
+/// Counters of CLI output we could not fully understand
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseStats {
+    pub lines: u64,
+    pub parse_failures: u64,
+    pub unknown_messages: u64,
+    pub unknown_blocks: u64,
+    /// Occurrences of each unrecognized `type` value
+    pub unknown_types: HashMap<String, u64>,
+    pub last_unparsed: Option<String>,
+}
+
+static PARSE_STATS: LazyLock<Mutex<ParseStats>> = LazyLock::new(Default::default);
+
+pub fn record_line() {
+    PARSE_STATS.lock().unwrap().lines += 1;
+}
+
+pub fn record_parse_failure(line: &str) {
+    let mut stats = PARSE_STATS.lock().unwrap();
+    stats.parse_failures += 1;
+    stats.last_unparsed = Some(sample(line));
+}
+
+pub fn record_unknown_message(raw: &Value) {
+    let mut stats = PARSE_STATS.lock().unwrap();
+    stats.unknown_messages += 1;
+    record_unknown(&mut stats, raw);
+}
+
+pub fn record_unknown_block(raw: &Value) {
+    let mut stats = PARSE_STATS.lock().unwrap();
+    stats.unknown_blocks += 1;
+    record_unknown(&mut stats, raw);
+}
+
+fn record_unknown(stats: &mut ParseStats, raw: &Value) {
+    let type_name = raw
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or("<missing>")
+        .to_string();
+    *stats.unknown_types.entry(type_name).or_default() += 1;
+    stats.last_unparsed = Some(sample(&raw.to_string()));
+}
+
+fn sample(line: &str) -> String {
+    match line.char_indices().nth(MAX_SAMPLE_LEN) {
+        Some((end, _)) => format!("{}...", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+/// Snapshot of the parse counters since startup
+pub fn parse_stats() -> ParseStats {
+    PARSE_STATS.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_versions_as_printed_by_the_cli() {
+        assert_eq!(parse_version("1.0.51 (Claude Code)"), Some((1, 0, 51)));
+        assert_eq!(parse_version("v2.1"), Some((2, 1, 0)));
+        assert_eq!(parse_version("3"), Some((3, 0, 0)));
+        assert_eq!(parse_version("2.0.0-beta.1"), Some((2, 0, 0)));
+    }
+
+    #[test]
+    fn rejects_versions_without_a_major_number() {
+        assert_eq!(parse_version(""), None);
+        assert_eq!(parse_version("   "), None);
+        assert_eq!(parse_version("Claude Code"), None);
+        assert_eq!(parse_version("é.1.2"), None);
+    }
+
+    #[test]
+    fn table_bounds_are_inclusive_then_exclusive() {
+        let status = |version: &str| CliCompatibility::check(version).status;
+        assert_eq!(status("0.9.99"), CompatibilityStatus::Unsupported);
+        assert_eq!(status("1.0.0"), CompatibilityStatus::Supported);
+        assert_eq!(status("2.99.99 (Claude Code)"), CompatibilityStatus::Supported);
+        assert_eq!(status("3.0.0"), CompatibilityStatus::Untested);
+    }
+
+    #[test]
+    fn unparseable_versions_are_untested() {
+        let compatibility = CliCompatibility::check("");
+        assert_eq!(compatibility.status, CompatibilityStatus::Untested);
+        assert_eq!(compatibility.version, "");
+        assert!(!compatibility.notes.is_empty());
+    }
+
+    #[test]
+    fn samples_are_cut_on_character_boundaries() {
+        let line = "é".repeat(MAX_SAMPLE_LEN + 1);
+        let cut = sample(&line);
+        assert!(cut.ends_with("..."));
+        assert_eq!(cut.chars().count(), MAX_SAMPLE_LEN + 3);
+        assert_eq!(sample("short"), "short");
+    }
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+
+use super::retry::ErrorClass;
+use crate::core::error::{ErrorCode, ForgeError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiagnosticKind {
+    NotAuthenticated,
+    NotInstalled,
+    InvalidFlag,
+    RateLimited,
+    Unknown,
+}
+
+impl DiagnosticKind {
+    pub fn error_code(self) -> ErrorCode {
+        match self {
+            Self::NotAuthenticated => ErrorCode::NotAuthenticated,
+            Self::NotInstalled => ErrorCode::CliNotFound,
+            Self::InvalidFlag => ErrorCode::CliRejectedArguments,
+            Self::RateLimited => ErrorCode::RateLimited,
+            Self::Unknown => ErrorCode::CliFailed,
+        }
+    }
+}
+
+/// Explanation of a failed CLI run that the UI can act on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliDiagnostic {
+    pub kind: DiagnosticKind,
+    pub summary: String,
+    pub hint: Option<String>,
+    pub error: Option<String>,
+    pub stderr: Option<String>,
+}
+
+impl CliDiagnostic {
+    /// Diagnose a failed result from its error text and the process's stderr
+    pub fn from_result(error: Option<&str>, stderr: Option<&str>) -> Self {
+        let text = format!("{} {}", error.unwrap_or_default(), stderr.unwrap_or_default()).to_lowercase();
+
+        let kind = if text.contains("not authenticated")
+            || text.contains("invalid api key")
+            || text.contains("/login")
+            || text.contains("authentication_error")
+            || text.contains("oauth token")
+        {
+            DiagnosticKind::NotAuthenticated
+        } else if text.contains("command not found") || text.contains("claude: not found") {
+            DiagnosticKind::NotInstalled
+        } else if text.contains("unknown option")
+            || text.contains("unexpected argument")
+            || text.contains("invalid value")
+            || text.contains("error: option")
+        {
+            DiagnosticKind::InvalidFlag
+        } else if text.contains("usage limit")
+            || ErrorClass::classify("", error, stderr) == ErrorClass::RateLimited
+        {
+            DiagnosticKind::RateLimited
+        } else {
+            DiagnosticKind::Unknown
+        };
+
+        Self::new(kind, error, stderr)
+    }
+
+    /// Diagnose a failure to start the CLI process at all
+    pub fn from_spawn_error(err: &anyhow::Error) -> Self {
+        let not_found = err
+            .downcast_ref::<io::Error>()
+            .is_some_and(|e| e.kind() == io::ErrorKind::NotFound);
+
+        if not_found {
+            Self::new(DiagnosticKind::NotInstalled, Some(&err.to_string()), None)
+        } else {
+            Self::from_result(Some(&err.to_string()), None)
+        }
+    }
+
+    /// Coded error carrying this diagnostic as its details
+    pub fn to_error(&self) -> ForgeError {
+        ForgeError::new(self.kind.error_code(), self.summary.clone()).with_details(self)
+    }
+
+    fn new(kind: DiagnosticKind, error: Option<&str>, stderr: Option<&str>) -> Self {
+        let (summary, hint) = match kind {
+            DiagnosticKind::NotAuthenticated => (
+                "Claude CLI is not authenticated",
+                Some("Run `claude` in a terminal and log in, then try again"),
+            ),
+            DiagnosticKind::NotInstalled => (
+                "Claude CLI is not installed",
+                Some("Install it from https://claude.ai/cli and make sure `claude` is on your PATH"),
+            ),
+            DiagnosticKind::InvalidFlag => (
+                "Claude CLI rejected its command line arguments",
+                Some("Your Claude CLI may be outdated - update it with `claude update`"),
+            ),
+            DiagnosticKind::RateLimited => (
+                "Claude API rate limit reached",
+                Some("Wait a moment before sending another message"),
+            ),
+            DiagnosticKind::Unknown => (error.unwrap_or("Claude CLI failed"), None),
+        };
+
+        Self {
+            kind,
+            summary: summary.to_string(),
+            hint: hint.map(str::to_string),
+            error: error.map(str::to_string),
+            stderr: stderr.map(str::to_string),
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use super::manager::{ForkOrigin, Message};
+use super::summary::TurnSummary;
+use crate::core::error::{ErrorCode, ForgeError};
+
+/// Version of the JSON export format, bumped on incompatible changes
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+    Html,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+            ExportFormat::Html => "html",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Html => "HTML",
+        }
+    }
+}
+
+/// Everything stored for a session; the JSON format is this struct as-is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationExport {
+    pub version: u32,
+    pub session_id: String,
+    pub agent_id: String,
+    pub cli_session_id: Option<String>,
+    pub working_directory: Option<String>,
+    pub forked_from: Option<ForkOrigin>,
+    pub exported_at: DateTime<Utc>,
+    pub messages: Vec<Message>,
+    pub turns: Vec<TurnSummary>,
+}
+
+impl ConversationExport {
+    pub fn render(&self, format: ExportFormat) -> Result<String> {
+        match format {
+            ExportFormat::Markdown => Ok(render_markdown(self)),
+            ExportFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            ExportFormat::Html => Ok(render_html(self)),
+        }
+    }
+
+    pub fn write(&self, format: ExportFormat, path: &Path) -> Result<()> {
+        fs::write(path, self.render(format)?)?;
+        info!(
+            "Exported session {} as {} to {}",
+            self.session_id,
+            format.label(),
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// Read a JSON export back
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let export: Self = serde_json::from_str(&contents)
+            .map_err(|e| {
+                ForgeError::new(ErrorCode::InvalidInput, format!("Not a conversation export {}: {}", path.display(), e))
+            })?;
+        if export.version > EXPORT_FORMAT_VERSION {
+            return Err(ForgeError::new(
+                ErrorCode::InvalidInput,
+                format!("Export format version {} is newer than this version of Forge supports", export.version),
+            )
+            .into());
+        }
+        Ok(export)
+    }
+}
+
+/// One rendered entry: streamed assistant chunks are joined into the reply they belong to
+enum Entry<'a> {
+    User(&'a Message),
+    Assistant(String),
+    Tool(&'a Message),
+    Notice(&'a Message),
+}
+
+fn entries(messages: &[Message]) -> Vec<Entry<'_>> {
+    let mut entries = Vec::new();
+    for message in messages {
+        match message.role.as_str() {
+            "user" => entries.push(Entry::User(message)),
+            "assistant" => entries.push(Entry::Assistant(message.content.clone())),
+            "assistant_stream" => match entries.last_mut() {
+                Some(Entry::Assistant(text)) => text.push_str(&message.content),
+                _ => entries.push(Entry::Assistant(message.content.clone())),
+            },
+            _ if message.tool.is_some() => entries.push(Entry::Tool(message)),
+            _ => entries.push(Entry::Notice(message)),
+        }
+    }
+    entries
+}
+
+fn tool_input(message: &Message) -> String {
+    message
+        .tool
+        .as_ref()
+        .and_then(|tool| serde_json::to_string_pretty(&tool.input).ok())
+        .unwrap_or_default()
+}
+
+fn render_markdown(export: &ConversationExport) -> String {
+    let mut out = format!("# Conversation {}\n\n", export.session_id);
+    out.push_str(&format!("- Agent: {}\n", export.agent_id));
+    if let Some(cwd) = &export.working_directory {
+        out.push_str(&format!("- Working directory: `{}`\n", cwd));
+    }
+    out.push_str(&format!("- Exported: {}\n", export.exported_at.to_rfc3339()));
+
+    for entry in entries(&export.messages) {
+        match entry {
+            Entry::User(message) => {
+                out.push_str(&format!("\n## User\n\n{}\n", message.content));
+            }
+            Entry::Assistant(text) => {
+                out.push_str(&format!("\n## Assistant\n\n{}\n", text));
+            }
+            Entry::Tool(message) => {
+                let input = tool_input(message);
+                let fence = fence(&input);
+                out.push_str(&format!(
+                    "\n<details>\n<summary>{}</summary>\n\n{}json\n{}\n{}\n\n</details>\n",
+                    escape_html(&message.content),
+                    fence,
+                    input,
+                    fence
+                ));
+            }
+            Entry::Notice(message) => {
+                out.push_str(&format!("\n> {}\n", message.content.replace('\n', "\n> ")));
+            }
+        }
+    }
+
+    out
+}
+
+/// A code fence longer than any run of backticks in `content`, so the content can't close it
+fn fence(content: &str) -> String {
+    let longest = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat((longest + 1).max(3))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const HTML_STYLE: &str = "\
+body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif; max-width: 860px; margin: 2rem auto; padding: 0 1rem; color: #1f2328; }
+header { border-bottom: 1px solid #d0d7de; margin-bottom: 1.5rem; }
+header p { color: #59636e; margin: 0.25rem 0; }
+.message { border-radius: 8px; padding: 0.75rem 1rem; margin: 0.75rem 0; white-space: pre-wrap; }
+.user { background: #ddf4ff; }
+.assistant { background: #f6f8fa; }
+.role { font-weight: 600; font-size: 0.85rem; margin-bottom: 0.25rem; white-space: normal; }
+.notice { color: #59636e; font-size: 0.9rem; margin: 0.5rem 0; }
+details { margin: 0.5rem 0; font-size: 0.9rem; }
+pre { background: #f6f8fa; padding: 0.75rem; border-radius: 6px; overflow-x: auto; }
+";
+
+fn render_html(export: &ConversationExport) -> String {
+    let mut body = String::new();
+    for entry in entries(&export.messages) {
+        match entry {
+            Entry::User(message) => body.push_str(&format!(
+                "<div class=\"message user\"><div class=\"role\">User</div>{}</div>\n",
+                escape_html(&message.content)
+            )),
+            Entry::Assistant(text) => body.push_str(&format!(
+                "<div class=\"message assistant\"><div class=\"role\">Assistant</div>{}</div>\n",
+                escape_html(&text)
+            )),
+            Entry::Tool(message) => body.push_str(&format!(
+                "<details><summary>{}</summary><pre>{}</pre></details>\n",
+                escape_html(&message.content),
+                escape_html(&tool_input(message))
+            )),
+            Entry::Notice(message) => body.push_str(&format!(
+                "<div class=\"notice\">{}</div>\n",
+                escape_html(&message.content)
+            )),
+        }
+    }
+
+    let working_directory = export
+        .working_directory
+        .as_deref()
+        .map(|cwd| format!("<p>Working directory: <code>{}</code></p>", escape_html(cwd)))
+        .unwrap_or_default();
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Conversation {id}</title>\n<style>\n{style}</style>\n</head>\n<body>\n<header>\n<h1>Conversation {id}</h1>\n<p>Agent: {agent}</p>\n{cwd}\n<p>Exported: {exported}</p>\n</header>\n{body}</body>\n</html>\n",
+        id = escape_html(&export.session_id),
+        style = HTML_STYLE,
+        agent = escape_html(&export.agent_id),
+        cwd = working_directory,
+        exported = export.exported_at.to_rfc3339(),
+        body = body,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fence_outlasts_backticks_in_the_content() {
+        assert_eq!(fence(r#"{"command": "ls"}"#), "```");
+        assert_eq!(fence(r#"{"content": "use `x` here"}"#), "```");
+        assert_eq!(fence("```rust\nfn main() {}\n```"), "````");
+        assert_eq!(fence("`````"), "``````");
+    }
+}
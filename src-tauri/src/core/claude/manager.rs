@@ -1,14 +1,30 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::async_runtime::Mutex;
 use uuid::Uuid;
-use tokio::sync::mpsc;
-use tracing::{debug, error, info, warn};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error, info, warn, Instrument};
 
+use super::activity::ActivityTracker;
 use super::cli_process::{ClaudeCliMessage, ClaudeCliOptions, ClaudeCliProcess};
+use super::cli_sessions;
+use crate::core::agents::{AgentType, PermissionMode};
+use crate::core::error::{ErrorCode, ForgeError};
+use crate::core::git;
+use crate::core::mcp::McpRegistry;
+use crate::core::projects::{ProjectRegistry, ProjectWorktree};
+use crate::core::search::{SearchDocument, SearchHit, SearchIndex, SearchQuery};
+use super::diagnostics::CliDiagnostic;
+use super::export::{ConversationExport, EXPORT_FORMAT_VERSION};
+use super::retry::{ErrorClass, QueryAttempt, RetryPolicy};
+use super::state::{SessionInfo, SessionState, SessionStateChanged};
+use super::store::SessionStore;
+use super::summary::{TokenUsage, TurnSummary};
+
+/// Sent on retry in place of a prompt the failed run already saved to the CLI session
+const CONTINUE_PROMPT: &str = "Your previous response was interrupted by an error. Continue where you left off.";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct UserMessage {
@@ -23,6 +39,24 @@ pub struct Message {
     pub content: String,
     pub timestamp: DateTime<Utc>,
     pub session_id: String,
+    /// Position in the session's history, so a window can replay what it missed
+    #[serde(default)]
+    pub seq: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diagnostic: Option<CliDiagnostic>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<TurnSummary>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<ToolCall>,
+}
+
+/// A tool the assistant invoked, as sent by the CLI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub input: serde_json::Value,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,7 +65,11 @@ pub struct AgentConfig {
     pub id: String,
     pub name: String,
     #[serde(rename = "type")]
-    pub agent_type: String,
+    pub agent_type: AgentType,
+    /// Template the agent was created from
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Added to the CLI's own system prompt
     pub system_prompt: Option<String>,
     pub working_directory: Option<String>,
     pub branch: Option<String>,
@@ -39,9 +77,37 @@ pub struct AgentConfig {
     pub max_tokens: Option<i32>,
     pub temperature: Option<f32>,
     pub max_turns: Option<i32>,
+    #[serde(default)]
+    pub allowed_tools: Option<Vec<String>>,
+    #[serde(default)]
+    pub disallowed_tools: Option<Vec<String>>,
+    /// Queries run non-interactively, so nothing can answer a permission prompt. Permission
+    /// checks are always skipped; `plan` also denies the tools that change files, and
+    /// `default` and `acceptEdits` behave like `bypassPermissions`.
+    #[serde(default)]
+    pub permission_mode: Option<PermissionMode>,
+    #[serde(default)]
+    pub retry_policy: Option<RetryPolicy>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl AgentConfig {
+    /// Query options the agent's settings imply, as the frontend applies them
+    pub fn query_options(&self) -> QueryOptions {
+        QueryOptions {
+            cwd: self.working_directory.clone(),
+            append_system_prompt: self.system_prompt.clone(),
+            model: self.model.clone(),
+            max_turns: self.max_turns,
+            allowed_tools: self.allowed_tools.clone(),
+            disallowed_tools: self.disallowed_tools.clone(),
+            permission_mode: self.permission_mode.map(|mode| mode.as_str().to_string()),
+            retry_policy: self.retry_policy.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryOptions {
     pub allowed_tools: Option<Vec<String>>,
@@ -54,38 +120,188 @@ pub struct QueryOptions {
     pub custom_system_prompt: Option<String>,
     pub append_system_prompt: Option<String>,
     pub permission_mode: Option<String>,
+    pub retry_policy: Option<RetryPolicy>,
+    /// Snapshot the worktree before the query so its changes can be rolled back; on unless false
+    pub checkpoint: Option<bool>,
 }
 
 pub struct ClaudeSession {
     pub id: String,
+    pub agent_id: String,
+    /// Project the session's queries are confined to
+    pub project_id: Option<String>,
     pub messages: Vec<Message>,
     pub cli_session_id: Option<String>,
     pub process: Option<ClaudeCliProcess>,
+    pub attempts: Vec<QueryAttempt>,
+    pub last_diagnostic: Option<CliDiagnostic>,
+    pub turns: Vec<TurnSummary>,
+    /// Directory of the most recent query
+    pub working_directory: Option<String>,
+    /// Worktree the session is pinned to; overrides the query's cwd
+    pub worktree: Option<String>,
+    pub forked_from: Option<ForkOrigin>,
+    /// Resume `cli_session_id` under a new CLI session id on the next run
+    pub fork_pending: bool,
+    /// Earlier conversation to replay to a fresh CLI session with the next prompt
+    pub seed_context: Option<String>,
+    pub state: SessionState,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub last_activity: DateTime<Utc>,
+    pub queued_prompts: usize,
+    /// Bumped by every abort; queries started before it stop instead of carrying on
+    aborts: u64,
+    /// Held for the duration of a query so the next one queues behind it
+    turn: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl ClaudeSession {
+    fn new(id: String, agent_id: &str) -> Self {
+        Self {
+            id,
+            agent_id: agent_id.to_string(),
+            project_id: None,
+            messages: Vec::new(),
+            cli_session_id: None,
+            process: None,
+            attempts: Vec::new(),
+            last_diagnostic: None,
+            turns: Vec::new(),
+            working_directory: None,
+            worktree: None,
+            forked_from: None,
+            fork_pending: false,
+            seed_context: None,
+            state: SessionState::Idle,
+            created_at: Utc::now(),
+            started_at: None,
+            last_activity: Utc::now(),
+            queued_prompts: 0,
+            aborts: 0,
+            turn: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    /// Record a state transition, returning the change if the state actually changed
+    pub(super) fn set_state(&mut self, state: SessionState) -> Option<SessionStateChanged> {
+        let now = Utc::now();
+        let previous = self.state;
+        self.last_activity = now;
+        if previous == state {
+            return None;
+        }
+
+        // Retries go back to starting within the same query
+        if state.is_active() && !previous.is_active() {
+            self.started_at = Some(now);
+        }
+        self.state = state;
+        Some(SessionStateChanged {
+            session_id: self.id.clone(),
+            agent_id: self.agent_id.clone(),
+            state,
+            previous,
+            timestamp: now,
+        })
+    }
+
+    /// Append a message to the history, stamping its sequence number
+    fn push_message(&mut self, mut message: Message) -> Message {
+        message.seq = self.messages.len() as u64;
+        self.last_activity = message.timestamp;
+        self.messages.push(message.clone());
+        message
+    }
+
+    fn info(&self) -> SessionInfo {
+        SessionInfo {
+            session_id: self.id.clone(),
+            agent_id: self.agent_id.clone(),
+            state: self.state,
+            project_id: self.project_id.clone(),
+            pid: self.process.as_ref().and_then(ClaudeCliProcess::pid),
+            working_directory: self.working_directory.clone(),
+            created_at: self.created_at,
+            started_at: self.started_at,
+            last_activity: self.last_activity,
+            message_count: self.messages.len(),
+            queued_prompts: self.queued_prompts,
+        }
+    }
+
+    /// CLI session to resume, whether to fork it, and the prompt to send
+    fn resume_target(&self, prompt: &str) -> (Option<String>, bool, String) {
+        let prompt = match &self.seed_context {
+            Some(seed) => format!("{}\n\n{}", seed, prompt),
+            None => prompt.to_string(),
+        };
+        (self.cli_session_id.clone(), self.fork_pending, prompt)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkOrigin {
+    pub session_id: String,
+    pub message_index: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkSessionOptions {
+    /// Last message to keep; defaults to the whole conversation
+    pub message_index: Option<usize>,
+    /// Run the fork in a new git worktree
+    pub worktree: Option<ForkWorktree>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkWorktree {
+    pub path: String,
+    pub branch: String,
+    /// Commit or branch to start from; defaults to HEAD of the parent's directory
+    pub base: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkedSession {
+    pub session_id: String,
+    pub working_directory: Option<String>,
+    /// Whether the fork continues the parent's CLI session or replays the transcript
+    pub carries_cli_session: bool,
 }
 
 pub struct ClaudeManager {
-    sessions: Arc<Mutex<HashMap<String, ClaudeSession>>>,
+    sessions: Arc<SessionStore>,
+    mcp_registry: Arc<McpRegistry>,
+    projects: Arc<ProjectRegistry>,
+    search_index: Arc<Mutex<SearchIndex>>,
 }
 
 impl ClaudeManager {
-    pub fn new() -> Self {
+    pub fn new(mcp_registry: Arc<McpRegistry>, projects: Arc<ProjectRegistry>) -> Self {
         Self {
-            sessions: Arc::new(Mutex::new(HashMap::new())),
+            sessions: Arc::new(SessionStore::new()),
+            mcp_registry,
+            projects,
+            search_index: Arc::new(Mutex::new(SearchIndex::new())),
         }
     }
 
-    pub async fn create_session(&self, agent_id: &str) -> Result<String> {
+    /// Start a session, confined to a project's directories if one is given
+    pub async fn create_session(&self, agent_id: &str, project_id: Option<&str>) -> Result<String> {
         let session_id = format!("{}-{}", agent_id, Uuid::new_v4());
-        
-        let session = ClaudeSession {
-            id: session_id.clone(),
-            messages: Vec::new(),
-            cli_session_id: None,
-            process: None,
-        };
+        let mut session = ClaudeSession::new(session_id.clone(), agent_id);
 
-        let mut sessions = self.sessions.lock().await;
-        sessions.insert(session_id.clone(), session);
+        if let Some(project_id) = project_id {
+            let project = self.projects.get(project_id)?;
+            session.project_id = Some(project.id);
+            session.working_directory = Some(project.root_path);
+        }
+        self.sessions.insert(session);
 
         Ok(session_id)
     }
@@ -97,294 +313,1146 @@ impl ClaudeManager {
         options: Option<QueryOptions>,
         tx: tokio::sync::mpsc::Sender<Message>,
     ) -> Result<()> {
+        // Everything logged for this query, including its CLI processes and retries, carries these fields
+        let span = crate::span!(
+            "query",
+            session_id = %session_id,
+            agent_id = tracing::field::Empty,
+            pid = tracing::field::Empty
+        );
+        self.run_query(session_id, prompt, options, tx).instrument(span).await
+    }
+
+    async fn run_query(
+        &self,
+        session_id: &str,
+        prompt: &str,
+        options: Option<QueryOptions>,
+        tx: tokio::sync::mpsc::Sender<Message>,
+    ) -> Result<()> {
+        let options = options.unwrap_or_default();
+        let retry_policy = options.retry_policy.clone().unwrap_or_default();
+        let fallback_model = options.fallback_model.clone();
+        let checkpoint = options.checkpoint.unwrap_or(true);
+
         // Convert QueryOptions to ClaudeCliOptions
-        let cli_options = if let Some(opts) = options {
-            ClaudeCliOptions {
-                model: opts.model,
-                allowed_tools: opts.allowed_tools,
-                disallowed_tools: opts.disallowed_tools,
-                working_directory: opts.cwd,
+        let mut cli_options = ClaudeCliOptions {
+            model: options.model,
+            allowed_tools: options.allowed_tools,
+            disallowed_tools: options.disallowed_tools,
+            system_prompt: options.custom_system_prompt,
+            append_system_prompt: options.append_system_prompt,
+            max_turns: options.max_turns,
+            permission_mode: options.permission_mode,
+            working_directory: options.cwd,
+            mcp_config: None,
+            fork_session: false,
+        };
+
+        // Sessions in a project only run inside it, with the project's settings as defaults
+        let (project_id, aborts) = {
+            let session = self
+                .sessions
+                .get(session_id)
+                .ok_or_else(|| ForgeError::session_not_found(session_id))?;
+            let session = session.lock().await;
+            (session.project_id.clone(), session.aborts)
+        };
+        if let Some(project_id) = project_id {
+            let project = self.projects.get(&project_id)?;
+            if let Some(cwd) = &cli_options.working_directory {
+                project.check_directory(cwd)?;
             }
-        } else {
-            ClaudeCliOptions::default()
+            if cli_options.model.is_none() {
+                cli_options.model = project.settings.model.clone();
+            }
+            if cli_options.permission_mode.is_none() {
+                cli_options.permission_mode = project.settings.permission_mode.map(|mode| mode.as_str().to_string());
+            }
+        }
+
+        // One query at a time per session; later ones wait their turn
+        let turn = {
+            let session = self
+                .sessions
+                .get(session_id)
+                .ok_or_else(|| ForgeError::session_not_found(session_id))?;
+            let turn = session.lock().await.turn.clone();
+            turn
         };
+        let turn = match turn.clone().try_lock_owned() {
+            Ok(turn) => turn,
+            Err(_) => {
+                info!("Session is busy, queueing the prompt");
+                self.sessions.update(session_id, |session| session.queued_prompts += 1).await;
+                self.sessions.transition(session_id, SessionState::Queued).await;
+                let turn = turn.lock_owned().await;
+                self.sessions
+                    .update(session_id, |session| session.queued_prompts = session.queued_prompts.saturating_sub(1))
+                    .await;
+                turn
+            }
+        };
+        if aborted(&self.sessions, session_id, aborts).await {
+            info!("Session was aborted while the prompt was queued");
+            return Ok(());
+        }
+        self.sessions.transition(session_id, SessionState::Starting).await;
 
-        // Get session and CLI session ID
-        let cli_session_id = {
-            let sessions = self.sessions.lock().await;
-            let session = sessions
+        // Get session and CLI session ID, and record the prompt in the session's history
+        let (cli_session_id, agent_id, cli_prompt, user_message) = {
+            let session = self
+                .sessions
                 .get(session_id)
-                .ok_or_else(|| anyhow!("Session not found"))?;
-            session.cli_session_id.clone()
+                .ok_or_else(|| ForgeError::session_not_found(session_id))?;
+            let mut session = session.lock().await;
+
+            if session.worktree.is_some() {
+                cli_options.working_directory = session.worktree.clone();
+            } else if cli_options.working_directory.is_none() {
+                cli_options.working_directory = session.working_directory.clone();
+            }
+            session.working_directory = cli_options.working_directory.clone();
+            let user_message = session.push_message(Message {
+                role: "user".to_string(),
+                content: prompt.to_string(),
+                timestamp: Utc::now(),
+                session_id: session_id.to_string(),
+                seq: 0,
+                diagnostic: None,
+                summary: None,
+                tool: None,
+            });
+
+            let (cli_session_id, fork_pending, cli_prompt) = session.resume_target(prompt);
+            cli_options.fork_session = fork_pending;
+            (cli_session_id, session.agent_id.clone(), cli_prompt, user_message)
+        };
+        tracing::Span::current().record("agent_id", agent_id.as_str());
+
+        // Give the CLI the MCP servers enabled for this agent
+        let mcp_config = match self.mcp_registry.write_cli_config(&agent_id, session_id) {
+            Ok(file) => file,
+            Err(e) => {
+                self.sessions.transition(session_id, SessionState::Failed).await;
+                return Err(e);
+            }
+        };
+        cli_options.mcp_config = mcp_config.as_ref().map(|file| file.path().to_path_buf());
+
+        // Snapshot the worktree so the turn's changes can be rolled back. A turn is never
+        // held up by a failed snapshot.
+        let checkpoint_directory = cli_options.working_directory.clone().filter(|_| checkpoint);
+        let checkpoint_id = match checkpoint_directory {
+            Some(directory) => {
+                let label: String = prompt.lines().next().unwrap_or_default().chars().take(72).collect();
+                let seq = user_message.seq;
+                let session = session_id.to_string();
+                let checkpoint = tokio::task::spawn_blocking(move || {
+                    git::create_checkpoint(&directory, &label, Some(&session), Some(seq))
+                })
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|checkpoint| checkpoint);
+                match checkpoint {
+                    Ok(checkpoint) => checkpoint.map(|checkpoint| checkpoint.id),
+                    Err(e) => {
+                        warn!("Failed to checkpoint the worktree: {}", e);
+                        None
+                    }
+                }
+            }
+            None => None,
         };
 
-        // User message will be handled by the frontend, no need to send it from here
+        // The frontend shows the prompt as soon as it is sent, but needs its seq to spot gaps
+        let _ = tx.send(user_message).await;
 
         // Create channel for CLI messages
         let (cli_tx, mut cli_rx) = mpsc::channel(100);
         
         // Spawn Claude CLI process
-        let process = ClaudeCliProcess::spawn(
-            prompt,
+        let process = match ClaudeCliProcess::spawn(
+            &cli_prompt,
             cli_session_id.as_deref(),
             &cli_options,
             cli_tx,
-        ).await?;
+        ).await {
+            Ok(process) => process,
+            Err(e) => {
+                let diagnostic = CliDiagnostic::from_spawn_error(&e);
+                let error = diagnostic.to_error();
+                report_failure(&self.sessions, session_id, &tx, diagnostic, None).await;
+                return Err(error.into());
+            }
+        };
 
         // Store process in session
-        {
-            let mut sessions = self.sessions.lock().await;
-            if let Some(session) = sessions.get_mut(session_id) {
-                session.process = Some(process);
+        if let Some(process) = attach_process(&self.sessions, session_id, process, 1, &cli_options, aborts).await {
+            let _ = process.abort().await;
+            if self.sessions.contains(session_id) {
+                info!("Session was aborted while the CLI started");
+                return Ok(());
             }
+            return Err(ForgeError::session_not_found(session_id).into());
         }
+        self.sessions.transition(session_id, SessionState::Running).await;
 
-        // Process messages from CLI
-        let session_id_clone = session_id.to_string();
-        let tx_clone = tx.clone();
-        let sessions_clone = self.sessions.clone();
+        // Process messages from CLI, retrying transient failures
+        let session_id = session_id.to_string();
+        let prompt = prompt.to_string();
+        let sessions = self.sessions.clone();
         
         tokio::spawn(async move {
-            let mut assistant_content = String::new();
-            let mut _cli_session_id: Option<String> = None;
-            let mut is_first_assistant_message = true;
-            let mut total_tokens: Option<(u32, u32)> = None;
-            let mut last_message_id: Option<String> = None;
-            
-            while let Some(cli_msg) = cli_rx.recv().await {
-                match cli_msg {
-                    ClaudeCliMessage::System { session_id: sid, .. } => {
-                        if let Some(sid) = sid {
-                            _cli_session_id = Some(sid.clone());
-                            // Update session with CLI session ID
-                            let mut sessions = sessions_clone.lock().await;
-                            if let Some(session) = sessions.get_mut(&session_id_clone) {
-                                session.cli_session_id = Some(sid);
-                            }
-                        }
-                        // Don't send a processing message here - the UI already shows loading state
+            // Released when the query is over, letting the next queued one start
+            let _turn = turn;
+            // Deleted when the query is over, retries included
+            let _mcp_config = mcp_config;
+            let mut attempt = 1;
+            let mut activity = ActivityTracker::default();
+
+            loop {
+                let outcome = forward_cli_messages(&mut cli_rx, &session_id, &tx, &sessions, &mut activity).await;
+
+                // Clear process from session
+                let process = sessions
+                    .update(&session_id, |session| session.process.take())
+                    .await
+                    .flatten();
+                if let Some(process) = process {
+                    let _ = process.wait().await;
+                }
+                // The abort already set the session's state
+                if aborted(&sessions, &session_id, aborts).await {
+                    info!("Query was aborted");
+                    break;
+                }
+
+                let Some(outcome) = outcome else {
+                    info!("CLI run ended without a result, stopping");
+                    sessions.transition(&session_id, SessionState::Failed).await;
+                    break;
+                };
+
+                let error_class = outcome.is_error.then(|| {
+                    ErrorClass::classify(
+                        &outcome.subtype,
+                        outcome.error.as_deref().or(outcome.result.as_deref()),
+                        outcome.stderr.as_deref(),
+                    )
+                });
+                let retry = error_class.is_some_and(|class| retry_policy.should_retry(attempt, class));
+                record_attempt_outcome(&sessions, &session_id, error_class, outcome.error.clone(), retry).await;
+
+                if !retry {
+                    let summary = outcome.summarize(&session_id, attempt, &activity, checkpoint_id.clone());
+                    record_turn(&sessions, &session_id, summary.clone()).await;
+
+                    if outcome.is_error {
+                        let diagnostic = CliDiagnostic::from_result(outcome.error.as_deref(), outcome.stderr.as_deref());
+                        report_failure(&sessions, &session_id, &tx, diagnostic, Some(summary)).await;
+                    } else {
+                        // Send completion message with turn count, token usage and cost if available
+                        let mut complete_msg = system_message(&session_id, summary.describe());
+                        complete_msg.summary = Some(summary);
+                        emit(&sessions, &session_id, &tx, complete_msg).await;
+                        sessions.transition(&session_id, SessionState::Completed).await;
                     }
-                    ClaudeCliMessage::Assistant { message, .. } => {
-                        // Track token usage if available
-                        if let Some(usage) = &message.usage {
-                            total_tokens = Some((usage.input_tokens, usage.output_tokens));
-                        }
-                        
-                        // Check if this is an update to the same message
-                        let is_same_message = last_message_id.as_ref() == Some(&message.id);
-                        if !is_same_message {
-                            last_message_id = Some(message.id.clone());
-                            assistant_content.clear();
-                            is_first_assistant_message = true; // Reset for new message
+                    break;
+                }
+
+                let error = outcome.error.unwrap_or_else(|| "Unknown error".to_string());
+
+                let delay = retry_policy.backoff(attempt);
+                attempt += 1;
+
+                let mut notice = format!(
+                    "⏳ {} - retrying in {}s (attempt {}/{})",
+                    error,
+                    delay.as_secs(),
+                    attempt,
+                    retry_policy.max_attempts
+                );
+                if retry_policy.use_fallback_model {
+                    if let Some(fallback) = &fallback_model {
+                        if cli_options.model.as_ref() != Some(fallback) {
+                            notice.push_str(&format!(" using fallback model {}", fallback));
+                            cli_options.model = Some(fallback.clone());
                         }
-                        
-                        // Extract and stream content immediately
-                        for content in &message.content {
-                            use super::cli_process::ContentBlock;
-                            match content {
-                                ContentBlock::Text { text } => {
-                                    if !text.is_empty() {
-                                        // Check if we've already sent this exact content
-                                        if !is_same_message || !assistant_content.contains(text) {
-                                            assistant_content.push_str(text);
-                                            
-                                            // Send streaming message only if this is new content
-                                            let stream_msg = Message {
-                                                role: if is_first_assistant_message { 
-                                                    is_first_assistant_message = false;
-                                                    "assistant".to_string()
-                                                } else {
-                                                    "assistant_stream".to_string()
-                                                },
-                                                content: text.clone(),
-                                                timestamp: Utc::now(),
-                                                session_id: session_id_clone.clone(),
-                                            };
-                                            let _ = tx_clone.send(stream_msg).await;
-                                        }
-                                    }
-                                }
-                                ContentBlock::ToolUse { name, input, id: _ } => {
-                                    // Create a user-friendly tool message
-                                    let tool_msg = match name.as_str() {
-                                        "Read" => {
-                                            if let Some(path) = input.get("file_path").and_then(|v| v.as_str()) {
-                                                format!("📖 Reading file: {}", path)
-                                            } else {
-                                                format!("📖 Reading file")
-                                            }
-                                        }
-                                        "Write" => {
-                                            if let Some(path) = input.get("file_path").and_then(|v| v.as_str()) {
-                                                format!("✏️ Writing file: {}", path)
-                                            } else {
-                                                format!("✏️ Writing file")
-                                            }
-                                        }
-                                        "Edit" => {
-                                            if let Some(path) = input.get("file_path").and_then(|v| v.as_str()) {
-                                                format!("✏️ Editing file: {}", path)
-                                            } else {
-                                                format!("✏️ Editing file")
-                                            }
-                                        }
-                                        "Bash" => {
-                                            if let Some(cmd) = input.get("command").and_then(|v| v.as_str()) {
-                                                let cmd_preview = if cmd.len() > 50 {
-                                                    format!("{}...", &cmd[..50])
-                                                } else {
-                                                    cmd.to_string()
-                                                };
-                                                format!("💻 Running: {}", cmd_preview)
-                                            } else {
-                                                format!("💻 Running command")
-                                            }
-                                        }
-                                        "Task" => format!("🤖 Starting task"),
-                                        _ => format!("🔧 Using {}", name)
-                                    };
-                                    
-                                    assistant_content.push_str(&format!("\n{}\n", tool_msg));
-                                    
-                                    let tool_msg = Message {
-                                        role: "system".to_string(),
-                                        content: tool_msg,
-                                        timestamp: Utc::now(),
-                                        session_id: session_id_clone.clone(),
-                                    };
-                                    let _ = tx_clone.send(tool_msg).await;
-                                }
-                                ContentBlock::Thinking { .. } => {
-                                    // Don't show thinking process - it's internal to Claude
-                                }
-                                ContentBlock::Image { .. } => {
-                                    // Images are shown inline, no need for system message
-                                }
-                                ContentBlock::ServerToolUse { name, .. } => {
-                                    let tool_msg = format!("🔧 Using {}", name);
-                                    assistant_content.push_str(&format!("\n{}\n", tool_msg));
-                                    
-                                    let msg = Message {
-                                        role: "system".to_string(),
-                                        content: tool_msg,
-                                        timestamp: Utc::now(),
-                                        session_id: session_id_clone.clone(),
-                                    };
-                                    let _ = tx_clone.send(msg).await;
-                                }
-                                ContentBlock::McpToolUse { name, .. } => {
-                                    let tool_msg = format!("🔌 Using MCP: {}", name);
-                                    assistant_content.push_str(&format!("\n{}\n", tool_msg));
+                    }
+                }
+                warn!("Query attempt {} failed ({:?}): {}", attempt - 1, error_class, error);
+                emit(&sessions, &session_id, &tx, system_message(&session_id, notice)).await;
+
+                tokio::time::sleep(delay).await;
+                if aborted(&sessions, &session_id, aborts).await {
+                    info!("Query was aborted before it could be retried");
+                    break;
+                }
+
+                // Resume the CLI session the failed run was part of
+                sessions.transition(&session_id, SessionState::Starting).await;
+                let target = sessions
+                    .update(&session_id, |session| session.resume_target(&prompt))
+                    .await;
+                let Some((cli_session_id, fork_pending, mut cli_prompt)) = target else {
+                    info!("Session was removed, not retrying");
+                    break;
+                };
+                cli_options.fork_session = fork_pending;
+                // Sending the prompt again would put it in the transcript twice
+                if cli_session_id
+                    .as_deref()
+                    .is_some_and(|id| cli_sessions::ends_with_prompt(id, &cli_prompt))
+                {
+                    debug!("The failed run saved the prompt, asking the CLI to continue instead");
+                    cli_prompt = CONTINUE_PROMPT.to_string();
+                }
+
+                let (cli_tx, next_rx) = mpsc::channel(100);
+                let process = match ClaudeCliProcess::spawn(
+                    &cli_prompt,
+                    cli_session_id.as_deref(),
+                    &cli_options,
+                    cli_tx,
+                ).await {
+                    Ok(process) => process,
+                    Err(e) => {
+                        error!("Failed to restart Claude CLI: {}", e);
+                        let diagnostic = CliDiagnostic::from_spawn_error(&e);
+                        report_failure(&sessions, &session_id, &tx, diagnostic, None).await;
+                        break;
+                    }
+                };
+
+                if let Some(process) = attach_process(&sessions, &session_id, process, attempt, &cli_options, aborts).await {
+                    info!("Session was aborted or removed while retrying, aborting new process");
+                    let _ = process.abort().await;
+                    break;
+                }
+                sessions.transition(&session_id, SessionState::Running).await;
+                cli_rx = next_rx;
+            }
+            
+            info!("Message processing task completed");
+        }.in_current_span());
+
+        info!("Query method completed, message processing continues in background");
+        Ok(())
+    }
+
+    /// Adopt a session started with the CLI directly, continuing it with `--resume`
+    pub async fn import_cli_session(&self, agent_id: &str, cli_session_id: &str) -> Result<String> {
+        for session in self.sessions.all() {
+            let session = session.lock().await;
+            if session.cli_session_id.as_deref() == Some(cli_session_id) {
+                info!("CLI session {} is already imported as {}", cli_session_id, session.id);
+                return Ok(session.id.clone());
+            }
+        }
+
+        let info = cli_sessions::find_session(cli_session_id)?;
+        let transcript = cli_sessions::read_transcript(&info.path)?;
+
+        let session_id = format!("{}-{}", agent_id, Uuid::new_v4());
+        let mut session = ClaudeSession::new(session_id.clone(), agent_id);
+        session.cli_session_id = Some(info.cli_session_id.clone());
+        session.working_directory = info.working_directory.clone();
+        session.messages = transcript
+            .into_iter()
+            .enumerate()
+            .map(|(seq, entry)| Message {
+                role: entry.role,
+                content: entry.content,
+                timestamp: entry.timestamp,
+                session_id: session_id.clone(),
+                seq: seq as u64,
+                diagnostic: None,
+                summary: None,
+                tool: entry.tool,
+            })
+            .collect();
+
+        info!(
+            "Imported CLI session {} as {} ({} messages)",
+            cli_session_id,
+            session_id,
+            session.messages.len()
+        );
+        self.sessions.insert(session);
+
+        Ok(session_id)
+    }
+
+    /// Search all session transcripts, indexing messages added since the last search
+    pub async fn search(&self, query: &SearchQuery) -> Vec<SearchHit> {
+        let mut index = self.search_index.lock().await;
+        for session_id in index.session_ids() {
+            if !self.sessions.contains(&session_id) {
+                index.remove_session(&session_id);
+            }
+        }
+
+        for session in self.sessions.all() {
+            let session = session.lock().await;
+            if index.indexed_messages(&session.id) > session.messages.len() {
+                index.remove_session(&session.id);
+            }
+            let indexed = index.indexed_messages(&session.id);
+            for (message_index, message) in session.messages.iter().enumerate().skip(indexed) {
+                let document = search_document(&session, message_index, message);
+                if message.role == "assistant_stream" {
+                    index.extend_last(document);
+                } else {
+                    index.add(document);
+                }
+            }
+        }
+
+        index.search(query)
+    }
+
+    pub async fn session_info(&self, session_id: &str) -> Result<SessionInfo> {
+        let session = self
+            .sessions
+            .get(session_id)
+            .ok_or_else(|| ForgeError::session_not_found(session_id))?;
+        let info = session.lock().await.info();
+        Ok(info)
+    }
+
+    /// Messages after `since_seq`, or the whole history if it is `None`
+    pub async fn messages_since(&self, session_id: &str, since_seq: Option<u64>) -> Result<Vec<Message>> {
+        let session = self
+            .sessions
+            .get(session_id)
+            .ok_or_else(|| ForgeError::session_not_found(session_id))?;
+        let session = session.lock().await;
+        let skip = since_seq.map_or(0, |seq| seq as usize + 1);
+        Ok(session.messages.iter().skip(skip).cloned().collect())
+    }
+
+    /// Summaries of the session's finished queries, oldest first
+    pub async fn turns(&self, session_id: &str) -> Result<Vec<TurnSummary>> {
+        let session = self
+            .sessions
+            .get(session_id)
+            .ok_or_else(|| ForgeError::session_not_found(session_id))?;
+        let turns = session.lock().await.turns.clone();
+        Ok(turns)
+    }
+
+    /// Every session, oldest first
+    pub async fn list_sessions(&self) -> Vec<SessionInfo> {
+        let mut sessions = Vec::new();
+        for session in self.sessions.all() {
+            sessions.push(session.lock().await.info());
+        }
+        sessions.sort_by_key(|info| info.created_at);
+        sessions
+    }
+
+    /// Receive every session state transition
+    pub fn subscribe_state_changes(&self) -> broadcast::Receiver<SessionStateChanged> {
+        self.sessions.subscribe()
+    }
+
+    /// Snapshot of a session's stored transcript
+    pub async fn export_session(&self, session_id: &str) -> Result<ConversationExport> {
+        let session = self
+            .sessions
+            .get(session_id)
+            .ok_or_else(|| ForgeError::session_not_found(session_id))?;
+        let session = session.lock().await;
+
+        Ok(ConversationExport {
+            version: EXPORT_FORMAT_VERSION,
+            session_id: session.id.clone(),
+            agent_id: session.agent_id.clone(),
+            cli_session_id: session.cli_session_id.clone(),
+            working_directory: session.working_directory.clone(),
+            forked_from: session.forked_from.clone(),
+            exported_at: Utc::now(),
+            messages: session.messages.clone(),
+            turns: session.turns.clone(),
+        })
+    }
+
+    /// Restore an exported conversation as a new session
+    pub async fn import_conversation(&self, export: ConversationExport) -> Result<String> {
+        let session_id = format!("{}-{}", export.agent_id, Uuid::new_v4());
+        let mut session = ClaudeSession::new(session_id.clone(), &export.agent_id);
+        session.working_directory = export.working_directory;
+        session.forked_from = export.forked_from;
+        session.turns = export.turns;
+        session.messages = export
+            .messages
+            .into_iter()
+            .enumerate()
+            .map(|(seq, mut message)| {
+                message.session_id = session_id.clone();
+                message.seq = seq as u64;
+                message
+            })
+            .collect();
+
+        // Resume the CLI session if it exists on this machine, otherwise replay the transcript
+        match export.cli_session_id {
+            Some(cli_session_id) if cli_sessions::find_session(&cli_session_id).is_ok() => {
+                session.cli_session_id = Some(cli_session_id);
+            }
+            _ if !session.messages.is_empty() => {
+                session.seed_context = Some(render_transcript(&session.messages));
+            }
+            _ => {}
+        }
+
+        info!(
+            "Imported conversation {} as {} ({} messages)",
+            export.session_id,
+            session_id,
+            session.messages.len()
+        );
+        self.sessions.insert(session);
+
+        Ok(session_id)
+    }
+
+    /// Clone a session up to a message into a new session, optionally in a new worktree
+    pub async fn fork_session(
+        &self,
+        session_id: &str,
+        options: ForkSessionOptions,
+    ) -> Result<ForkedSession> {
+        let fork_id;
+        let mut fork;
+        let parent_cwd;
+        let parent_cli_session_id;
+        {
+            let parent = self
+                .sessions
+                .get(session_id)
+                .ok_or_else(|| ForgeError::session_not_found(session_id))?;
+            let parent = parent.lock().await;
+
+            let Some(last_index) = parent.messages.len().checked_sub(1) else {
+                return Err(ForgeError::new(ErrorCode::InvalidInput, "Cannot fork a session without messages").into());
+            };
+            let message_index = options.message_index.unwrap_or(last_index);
+            if message_index > last_index {
+                return Err(ForgeError::new(ErrorCode::InvalidInput, format!("Message {} is out of range", message_index)).into());
+            }
+
+            fork_id = format!("{}-{}", parent.agent_id, Uuid::new_v4());
+            fork = ClaudeSession::new(fork_id.clone(), &parent.agent_id);
+            fork.project_id = parent.project_id.clone();
+            fork.messages = parent.messages[..=message_index]
+                .iter()
+                .cloned()
+                .map(|mut message| {
+                    message.session_id = fork_id.clone();
+                    message
+                })
+                .collect();
+            let fork_point = fork.messages[message_index].timestamp;
+            fork.turns = parent
+                .turns
+                .iter()
+                .filter(|turn| turn.completed_at <= fork_point)
+                .cloned()
+                .collect();
+            fork.forked_from = Some(ForkOrigin {
+                session_id: session_id.to_string(),
+                message_index,
+            });
+
+            parent_cwd = parent.worktree.clone().or_else(|| parent.working_directory.clone());
+            fork.working_directory = parent_cwd.clone();
+
+            // The CLI can only branch off the end of a finished conversation
+            parent_cli_session_id = parent
+                .cli_session_id
+                .clone()
+                .filter(|_| message_index == last_index && parent.process.is_none());
+        }
+
+        if let Some(worktree) = &options.worktree {
+            let repo = parent_cwd
+                .as_deref()
+                .ok_or_else(|| ForgeError::new(ErrorCode::InvalidInput, "Session has no working directory to create a worktree from"))?;
+            let path = std::path::Path::new(repo).join(&worktree.path).to_string_lossy().to_string();
+            let _span = crate::span!("fork_session", session_id = %session_id, fork_id = %fork_id).entered();
+            git::add_worktree(repo, &path, &worktree.branch, worktree.base.as_deref())?;
+
+            if let Some(project_id) = &fork.project_id {
+                self.projects.add_worktree(
+                    project_id,
+                    ProjectWorktree {
+                        path: path.clone(),
+                        branch: worktree.branch.clone(),
+                        agent_id: Some(fork.agent_id.clone()),
+                        created_at: Utc::now(),
+                    },
+                )?;
+            }
+
+            fork.worktree = Some(path.clone());
+            fork.working_directory = Some(path);
+        }
+
+        let mut cli_session_id = parent_cli_session_id;
+        if let (Some(id), Some(from), Some(to)) = (&cli_session_id, &parent_cwd, &fork.worktree) {
+            // The CLI looks sessions up by directory, so the transcript has to move with the fork
+            if let Err(e) = cli_sessions::copy_session(id, from, to) {
+                warn!("Cannot carry CLI session into worktree, replaying transcript instead: {}", e);
+                cli_session_id = None;
+            }
+        }
+
+        let carries_cli_session = cli_session_id.is_some();
+        if carries_cli_session {
+            fork.cli_session_id = cli_session_id;
+            fork.fork_pending = true;
+        } else {
+            fork.seed_context = Some(render_transcript(&fork.messages));
+        }
+
+        info!(
+            "Forked session {} into {} ({} messages, carries CLI session: {})",
+            session_id,
+            fork_id,
+            fork.messages.len(),
+            carries_cli_session
+        );
+
+        let forked = ForkedSession {
+            session_id: fork_id.clone(),
+            working_directory: fork.working_directory.clone(),
+            carries_cli_session,
+        };
+        self.sessions.insert(fork);
+
+        Ok(forked)
+    }
+
+    /// Stop the running query and any queued ones. The session stays, in the aborted state.
+    pub async fn abort_session(&self, session_id: &str) -> Result<()> {
+        let process = self
+            .sessions
+            .update(session_id, |session| {
+                session.aborts += 1;
+                session.process.take()
+            })
+            .await;
+        let Some(process) = process else {
+            return Ok(());
+        };
+        self.sessions.transition(session_id, SessionState::Aborted).await;
+        if let Some(process) = process {
+            process.abort().await?;
+        }
+        Ok(())
+    }
+
+    /// Abort the session and forget it
+    pub async fn clear_session(&self, session_id: &str) -> Result<()> {
+        self.abort_session(session_id).await?;
+        self.sessions.remove(session_id);
+        Ok(())
+    }
+}
+
+/// Whether the session was aborted, or removed, since its abort count was `aborts`
+async fn aborted(sessions: &SessionStore, session_id: &str, aborts: u64) -> bool {
+    sessions.update(session_id, |session| session.aborts != aborts).await.unwrap_or(true)
+}
+
+/// Store a freshly spawned process in its session and record the attempt.
+/// Hands the process back if the session no longer exists or was aborted since `aborts`.
+async fn attach_process(
+    sessions: &SessionStore,
+    session_id: &str,
+    process: ClaudeCliProcess,
+    attempt: u32,
+    options: &ClaudeCliOptions,
+    aborts: u64,
+) -> Option<ClaudeCliProcess> {
+    let Some(session) = sessions.get(session_id) else {
+        return Some(process);
+    };
+    let mut session = session.lock().await;
+    if session.aborts != aborts {
+        return Some(process);
+    }
+
+    session.process = Some(process);
+    session.attempts.push(QueryAttempt {
+        attempt,
+        model: options.model.clone(),
+        started_at: Utc::now(),
+        finished_at: None,
+        error_class: None,
+        error: None,
+        retried: false,
+    });
+    None
+}
+
+async fn record_turn(
+    sessions: &SessionStore,
+    session_id: &str,
+    summary: TurnSummary,
+) {
+    sessions.update(session_id, |session| session.turns.push(summary)).await;
+}
+
+async fn record_attempt_outcome(
+    sessions: &SessionStore,
+    session_id: &str,
+    error_class: Option<ErrorClass>,
+    error: Option<String>,
+    retried: bool,
+) {
+    sessions
+        .update(session_id, |session| {
+            if let Some(attempt) = session.attempts.last_mut() {
+                attempt.finished_at = Some(Utc::now());
+                attempt.error_class = error_class;
+                attempt.error = error;
+                attempt.retried = retried;
+            }
+        })
+        .await;
+}
+
+/// Outcome of a single CLI run, taken from its result message
+struct CliOutcome {
+    subtype: String,
+    is_error: bool,
+    result: Option<String>,
+    error: Option<String>,
+    cli_session_id: Option<String>,
+    duration_ms: Option<u64>,
+    duration_api_ms: Option<u64>,
+    num_turns: Option<u32>,
+    total_cost_usd: Option<f64>,
+    usage: Option<TokenUsage>,
+    stderr: Option<String>,
+}
+
+impl CliOutcome {
+    fn summarize(
+        &self,
+        session_id: &str,
+        attempts: u32,
+        activity: &ActivityTracker,
+        checkpoint_id: Option<String>,
+    ) -> TurnSummary {
+        TurnSummary {
+            session_id: session_id.to_string(),
+            cli_session_id: self.cli_session_id.clone(),
+            subtype: self.subtype.clone(),
+            is_error: self.is_error,
+            result: self.result.clone(),
+            error: self.error.clone(),
+            num_turns: self.num_turns,
+            duration_ms: self.duration_ms,
+            duration_api_ms: self.duration_api_ms,
+            total_cost_usd: self.total_cost_usd,
+            usage: self.usage.clone(),
+            attempts,
+            activity: activity.finish(),
+            checkpoint_id,
+            completed_at: Utc::now(),
+        }
+    }
+}
+
+/// Forward one CLI run's output to the frontend until its result message arrives.
+/// Returns `None` if the run ended without a result (e.g. it was aborted).
+async fn forward_cli_messages(
+    cli_rx: &mut mpsc::Receiver<ClaudeCliMessage>,
+    session_id: &str,
+    tx: &mpsc::Sender<Message>,
+    sessions: &SessionStore,
+    activity: &mut ActivityTracker,
+) -> Option<CliOutcome> {
+    let mut assistant_content = String::new();
+    let mut cli_session_id: Option<String> = None;
+    let mut is_first_assistant_message = true;
+    let mut total_tokens: Option<(u32, u32)> = None;
+    let mut last_message_id: Option<String> = None;
+    
+    while let Some(cli_msg) = cli_rx.recv().await {
+        match cli_msg {
+            ClaudeCliMessage::System { session_id: sid, .. } => {
+                if let Some(sid) = sid {
+                    cli_session_id = Some(sid.clone());
+                    // Update session with CLI session ID; a fork or replay now has its own
+                    sessions
+                        .update(session_id, |session| {
+                            session.cli_session_id = Some(sid);
+                            session.fork_pending = false;
+                            session.seed_context = None;
+                        })
+                        .await;
+                }
+                // Don't send a processing message here - the UI already shows loading state
+            }
+            ClaudeCliMessage::Assistant { message, .. } => {
+                // Track token usage if available
+                if let Some(usage) = &message.usage {
+                    total_tokens = Some((usage.input_tokens, usage.output_tokens));
+                }
+                
+                // Check if this is an update to the same message
+                let is_same_message = last_message_id.as_ref() == Some(&message.id);
+                if !is_same_message {
+                    last_message_id = Some(message.id.clone());
+                    assistant_content.clear();
+                    is_first_assistant_message = true; // Reset for new message
+                }
+                
+                // Extract and stream content immediately
+                for content in &message.content {
+                    use super::cli_process::ContentBlock;
+                    match content {
+                        ContentBlock::Text { text } => {
+                            if !text.is_empty() {
+                                // Check if we've already sent this exact content
+                                if !is_same_message || !assistant_content.contains(text) {
+                                    assistant_content.push_str(text);
                                     
-                                    let msg = Message {
-                                        role: "system".to_string(),
-                                        content: tool_msg,
+                                    // Send streaming message only if this is new content
+                                    let stream_msg = Message {
+                                        role: if is_first_assistant_message { 
+                                            is_first_assistant_message = false;
+                                            "assistant".to_string()
+                                        } else {
+                                            "assistant_stream".to_string()
+                                        },
+                                        content: text.clone(),
                                         timestamp: Utc::now(),
-                                        session_id: session_id_clone.clone(),
+                                        session_id: session_id.to_string(),
+                                        seq: 0,
+                                        diagnostic: None,
+                                        summary: None,
+                                        tool: None,
                                     };
-                                    let _ = tx_clone.send(msg).await;
-                                }
-                                _ => {
-                                    // Ignore other content types
-                                }
-                            }
-                        }
-                    }
-                    ClaudeCliMessage::User { message, .. } => {
-                        // Parse user messages which contain tool results
-                        if let Ok(user_msg) = serde_json::from_value::<UserMessage>(message.clone()) {
-                            if let Some(content_array) = user_msg.content.as_array() {
-                                for content_val in content_array {
-                                    if let Ok(content) = serde_json::from_value::<super::cli_process::ContentBlock>(content_val.clone()) {
-                                        use super::cli_process::ContentBlock;
-                                        match content {
-                                            ContentBlock::ToolResult { tool_use_id: _, content, is_error } => {
-                                                // Only show errors or important results, not file contents
-                                                if is_error {
-                                                    let error_msg = content.as_deref().unwrap_or("Tool error occurred");
-                                                    let msg = Message {
-                                                        role: "system".to_string(),
-                                                        content: format!("❌ {}", error_msg),
-                                                        timestamp: Utc::now(),
-                                                        session_id: session_id_clone.clone(),
-                                                    };
-                                                    let _ = tx_clone.send(msg).await;
-                                                }
-                                                // Don't show successful tool results - they're usually large file contents
-                                            }
-                                            ContentBlock::WebSearchToolResult { .. } => {
-                                                // Web search results are shown inline by Claude, no need for system message
-                                            }
-                                            ContentBlock::CodeExecutionToolResult { .. } => {
-                                                // Code execution results are shown inline by Claude, no need for system message
-                                            }
-                                            _ => {}
-                                        }
-                                    }
+                                    emit(sessions, session_id, tx, stream_msg).await;
                                 }
                             }
                         }
-                    }
-                    ClaudeCliMessage::Result { is_error, error, duration_ms, .. } => {
-                        if is_error {
-                            let error_msg = Message {
+                        ContentBlock::ToolUse { name, input, id } => {
+                            activity.record_tool_use(id, name, input);
+
+                            // Create a user-friendly tool message
+                            let tool_msg = describe_tool_use(name, input);
+                            
+                            assistant_content.push_str(&format!("\n{}\n", tool_msg));
+                            
+                            let tool_msg = Message {
                                 role: "system".to_string(),
-                                content: format!("Error: {}", error.unwrap_or_else(|| "Unknown error".to_string())),
+                                content: tool_msg,
                                 timestamp: Utc::now(),
-                                session_id: session_id_clone.clone(),
+                                session_id: session_id.to_string(),
+                                seq: 0,
+                                diagnostic: None,
+                                summary: None,
+                                tool: Some(ToolCall {
+                                    id: id.clone(),
+                                    name: name.clone(),
+                                    input: input.clone(),
+                                }),
                             };
-                            let _ = tx_clone.send(error_msg).await;
-                        } else {
-                            // Send completion message with token usage if available
-                            let completion_content = if let Some((input, output)) = total_tokens {
-                                format!("Completed successfully (Tokens: {} in, {} out, {} total)", 
-                                    input, output, input + output)
-                            } else if let Some(duration) = duration_ms {
-                                format!("Completed successfully ({}ms)", duration)
-                            } else {
-                                "Completed successfully".to_string()
+                            emit(sessions, session_id, tx, tool_msg).await;
+                        }
+                        ContentBlock::Thinking { .. } => {
+                            // Don't show thinking process - it's internal to Claude
+                        }
+                        ContentBlock::Image { .. } => {
+                            // Images are shown inline, no need for system message
+                        }
+                        ContentBlock::ServerToolUse { id, name, input } => {
+                            let tool_msg = format!("🔧 Using {}", name);
+                            assistant_content.push_str(&format!("\n{}\n", tool_msg));
+                            
+                            let msg = Message {
+                                role: "system".to_string(),
+                                content: tool_msg,
+                                timestamp: Utc::now(),
+                                session_id: session_id.to_string(),
+                                seq: 0,
+                                diagnostic: None,
+                                summary: None,
+                                tool: Some(ToolCall {
+                                    id: id.clone(),
+                                    name: name.clone(),
+                                    input: input.clone(),
+                                }),
                             };
+                            emit(sessions, session_id, tx, msg).await;
+                        }
+                        ContentBlock::McpToolUse { id, name, input } => {
+                            let tool_msg = format!("🔌 Using MCP: {}", name);
+                            assistant_content.push_str(&format!("\n{}\n", tool_msg));
                             
-                            let complete_msg = Message {
+                            let msg = Message {
                                 role: "system".to_string(),
-                                content: completion_content,
+                                content: tool_msg,
                                 timestamp: Utc::now(),
-                                session_id: session_id_clone.clone(),
+                                session_id: session_id.to_string(),
+                                seq: 0,
+                                diagnostic: None,
+                                summary: None,
+                                tool: Some(ToolCall {
+                                    id: id.clone(),
+                                    name: name.clone(),
+                                    input: input.clone(),
+                                }),
                             };
-                            let _ = tx_clone.send(complete_msg).await;
+                            emit(sessions, session_id, tx, msg).await;
+                        }
+                        _ => {
+                            // Ignore other content types
                         }
-                        
-                        // Clear process from session
-                        let mut sessions = sessions_clone.lock().await;
-                        if let Some(session) = sessions.get_mut(&session_id_clone) {
-                            if let Some(process) = session.process.take() {
-                                let _ = process.wait().await;
+                    }
+                }
+            }
+            ClaudeCliMessage::User { message, .. } => {
+                // Parse user messages which contain tool results
+                if let Ok(user_msg) = serde_json::from_value::<UserMessage>(message.clone()) {
+                    if let Some(content_array) = user_msg.content.as_array() {
+                        for content_val in content_array {
+                            let parsed = serde_json::from_value::<super::cli_process::ContentBlock>(content_val.clone())
+                                .inspect_err(|e| {
+                                    warn!("Failed to parse tool result block: {}", e);
+                                    super::compat::record_parse_failure(&content_val.to_string());
+                                });
+                            if let Ok(content) = parsed {
+                                use super::cli_process::ContentBlock;
+                                match content {
+                                    ContentBlock::ToolResult { tool_use_id, content, is_error } => {
+                                        // Only show errors or important results, not file contents
+                                        if is_error {
+                                            activity.record_failure(&tool_use_id);
+                                            let error_msg = content.as_deref().unwrap_or("Tool error occurred");
+                                            let msg = Message {
+                                                role: "system".to_string(),
+                                                content: format!("❌ {}", error_msg),
+                                                timestamp: Utc::now(),
+                                                session_id: session_id.to_string(),
+                                                seq: 0,
+                                                diagnostic: None,
+                                                summary: None,
+                                                tool: None,
+                                            };
+                                            emit(sessions, session_id, tx, msg).await;
+                                        }
+                                        // Don't show successful tool results - they're usually large file contents
+                                    }
+                                    ContentBlock::WebSearchToolResult { .. } => {
+                                        // Web search results are shown inline by Claude, no need for system message
+                                    }
+                                    ContentBlock::CodeExecutionToolResult { .. } => {
+                                        // Code execution results are shown inline by Claude, no need for system message
+                                    }
+                                    _ => {}
+                                }
                             }
                         }
-                        break;
                     }
                 }
             }
-            
-            info!("Message processing task completed");
-        });
+            ClaudeCliMessage::Unknown(_) => {
+                // Already counted by the reader
+            }
+            ClaudeCliMessage::Result {
+                subtype,
+                is_error,
+                result,
+                error,
+                session_id: sid,
+                duration_ms,
+                duration_api_ms,
+                num_turns,
+                total_cost_usd,
+                usage,
+                stderr,
+            } => {
+                // Prefer the cumulative usage of the result over the last assistant message's
+                let usage = usage
+                    .as_ref()
+                    .map(TokenUsage::from)
+                    .or_else(|| {
+                        total_tokens.map(|(input_tokens, output_tokens)| TokenUsage {
+                            input_tokens,
+                            output_tokens,
+                            ..Default::default()
+                        })
+                    });
 
-        info!("Query method completed, message processing continues in background");
-        Ok(())
+                return Some(CliOutcome {
+                    subtype,
+                    is_error,
+                    result,
+                    error,
+                    cli_session_id: sid.or(cli_session_id),
+                    duration_ms,
+                    duration_api_ms,
+                    num_turns,
+                    total_cost_usd,
+                    usage,
+                    stderr,
+                });
+            }
+        }
     }
 
-    pub async fn abort_session(&self, session_id: &str) -> Result<()> {
-        let mut sessions = self.sessions.lock().await;
-        if let Some(mut session) = sessions.remove(session_id) {
-            // Abort the CLI process if running
-            if let Some(process) = session.process.take() {
-                process.abort().await?;
+    None
+}
+
+/// User-friendly one-line description of a tool call
+pub(super) fn describe_tool_use(name: &str, input: &serde_json::Value) -> String {
+    match name {
+        "Read" => {
+            if let Some(path) = input.get("file_path").and_then(|v| v.as_str()) {
+                format!("📖 Reading file: {}", path)
+            } else {
+                format!("📖 Reading file")
             }
         }
-        Ok(())
+        "Write" => {
+            if let Some(path) = input.get("file_path").and_then(|v| v.as_str()) {
+                format!("✏️ Writing file: {}", path)
+            } else {
+                format!("✏️ Writing file")
+            }
+        }
+        "Edit" => {
+            if let Some(path) = input.get("file_path").and_then(|v| v.as_str()) {
+                format!("✏️ Editing file: {}", path)
+            } else {
+                format!("✏️ Editing file")
+            }
+        }
+        "Bash" => {
+            if let Some(cmd) = input.get("command").and_then(|v| v.as_str()) {
+                let cmd_preview = if cmd.chars().count() > 50 {
+                    format!("{}...", cmd.chars().take(50).collect::<String>())
+                } else {
+                    cmd.to_string()
+                };
+                format!("💻 Running: {}", cmd_preview)
+            } else {
+                format!("💻 Running command")
+            }
+        }
+        "Task" => format!("🤖 Starting task"),
+        _ => format!("🔧 Using {}", name)
     }
+}
 
-    pub async fn clear_session(&self, session_id: &str) -> Result<()> {
-        self.abort_session(session_id).await
+fn search_document(session: &ClaudeSession, message_index: usize, message: &Message) -> SearchDocument {
+    // Collect the string arguments of a tool call: file paths, commands, patterns, URLs
+    fn strings(value: &serde_json::Value, out: &mut Vec<String>) {
+        match value {
+            serde_json::Value::String(s) => out.push(s.clone()),
+            serde_json::Value::Array(items) => items.iter().for_each(|item| strings(item, out)),
+            serde_json::Value::Object(map) => map.values().for_each(|item| strings(item, out)),
+            _ => {}
+        }
+    }
+
+    let tool_input = message.tool.as_ref().map(|tool| {
+        let mut out = Vec::new();
+        strings(&tool.input, &mut out);
+        out.join("\n")
+    });
+
+    SearchDocument {
+        session_id: session.id.clone(),
+        agent_id: session.agent_id.clone(),
+        message_index,
+        role: if message.role == "assistant_stream" { "assistant".to_string() } else { message.role.clone() },
+        content: message.content.clone(),
+        tool: message.tool.as_ref().map(|tool| tool.name.clone()),
+        tool_input,
+        working_directory: session.working_directory.clone(),
+        timestamp: message.timestamp,
+    }
+}
+
+/// Render a conversation as context for a fresh CLI session
+fn render_transcript(messages: &[Message]) -> String {
+    let mut transcript = String::from(
+        "This conversation continues an earlier one. Here is what was said so far:\n",
+    );
+    for message in messages {
+        match message.role.as_str() {
+            "user" => transcript.push_str(&format!("\nUser: {}\n", message.content)),
+            "assistant" => transcript.push_str(&format!("\nAssistant: {}", message.content)),
+            "assistant_stream" => transcript.push_str(&message.content),
+            _ => transcript.push_str(&format!("\n[{}]\n", message.content)),
+        }
+    }
+    transcript.push_str("\n\nContinue the conversation from here.");
+    transcript
+}
+
+/// Record a message in the session's history and send it to the frontend
+async fn emit(
+    sessions: &SessionStore,
+    session_id: &str,
+    tx: &mpsc::Sender<Message>,
+    message: Message,
+) {
+    // Without a session there is no seq to give it, and no window to show it in
+    let Some(message) = sessions.update(session_id, |session| session.push_message(message)).await else {
+        debug!("Session {} is gone, dropping message", session_id);
+        return;
+    };
+    let _ = tx.send(message).await;
+}
+
+fn system_message(session_id: &str, content: impl Into<String>) -> Message {
+    Message {
+        role: "system".to_string(),
+        content: content.into(),
+        timestamp: Utc::now(),
+        session_id: session_id.to_string(),
+        seq: 0,
+        diagnostic: None,
+        summary: None,
+        tool: None,
+    }
+}
+
+/// Tell the frontend why a query failed and keep the diagnosis with the session
+async fn report_failure(
+    sessions: &SessionStore,
+    session_id: &str,
+    tx: &mpsc::Sender<Message>,
+    diagnostic: CliDiagnostic,
+    summary: Option<TurnSummary>,
+) {
+    warn!("Query failed ({:?}): {}", diagnostic.kind, diagnostic.summary);
+    if let Some(stderr) = &diagnostic.stderr {
+        debug!("CLI stderr: {}", stderr);
     }
-}
\ No newline at end of file
+
+    let content = match &diagnostic.error {
+        Some(error) if error != &diagnostic.summary => format!("Error: {} ({})", diagnostic.summary, error),
+        _ => format!("Error: {}", diagnostic.summary),
+    };
+
+    sessions
+        .update(session_id, |session| session.last_diagnostic = Some(diagnostic.clone()))
+        .await;
+    sessions.transition(session_id, SessionState::Failed).await;
+
+    let mut message = system_message(session_id, content);
+    message.diagnostic = Some(diagnostic);
+    message.summary = summary;
+    emit(sessions, session_id, tx, message).await;
+}
@@ -1,4 +1,19 @@
 mod manager;
+mod activity;
 mod cli_process;
+mod cli_sessions;
+mod compat;
+mod diagnostics;
+mod export;
+mod retry;
+mod state;
+mod store;
+mod summary;
 
-pub use manager::{ClaudeManager, Message, QueryOptions};
\ No newline at end of file
+pub use cli_sessions::{list_sessions as list_cli_sessions, CliSessionInfo};
+pub use compat::{parse_stats, CliCompatibility, ParseStats};
+pub use export::{ConversationExport, ExportFormat};
+pub use manager::{AgentConfig, ClaudeManager, ForkSessionOptions, ForkedSession, Message, QueryOptions};
+pub use activity::{BashCommand, TurnActivity};
+pub use state::{SessionInfo, SessionState, SessionStateChanged};
+pub use summary::{TokenUsage, TurnSummary};
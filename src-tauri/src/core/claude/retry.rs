@@ -0,0 +1,207 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Broad category of a failed CLI run, used to decide whether it is worth retrying
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorClass {
+    Overloaded,
+    RateLimited,
+    Network,
+    Other,
+}
+
+/// How the CLI prefixes errors from the API, e.g. `API Error: 529 {"type":"error",...}`
+const API_ERROR_PREFIX: &str = "API Error:";
+/// Subtype of the result we make up for a CLI that exited without one
+pub(super) const INTERRUPTED_SUBTYPE: &str = "interrupted";
+/// Node's codes for connections that failed or dropped
+const NETWORK_ERROR_CODES: [&str; 6] = ["ECONNRESET", "ECONNREFUSED", "ETIMEDOUT", "ENOTFOUND", "EAI_AGAIN", "EPIPE"];
+
+impl ErrorClass {
+    /// Classify a failed run from the API error its result reports: the HTTP status or error
+    /// type, or the CLI's own connection and timeout errors. A run that died without a result
+    /// only has its stderr, where Node's network error codes are looked for.
+    pub fn classify(subtype: &str, message: Option<&str>, stderr: Option<&str>) -> Self {
+        if let Some(class) = message.and_then(Self::from_api_error) {
+            return class;
+        }
+        let network_failure = stderr.is_some_and(|stderr| {
+            stderr
+                .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .any(|word| NETWORK_ERROR_CODES.contains(&word))
+        });
+        if subtype == INTERRUPTED_SUBTYPE && network_failure {
+            ErrorClass::Network
+        } else {
+            ErrorClass::Other
+        }
+    }
+
+    fn from_api_error(message: &str) -> Option<Self> {
+        let start = message.find(API_ERROR_PREFIX)? + API_ERROR_PREFIX.len();
+        let detail = message[start..].trim_start();
+
+        if detail.contains("\"overloaded_error\"") {
+            return Some(ErrorClass::Overloaded);
+        }
+        if detail.contains("\"rate_limit_error\"") {
+            return Some(ErrorClass::RateLimited);
+        }
+        match detail.get(..3).and_then(|status| status.parse::<u16>().ok()) {
+            Some(529 | 503) => Some(ErrorClass::Overloaded),
+            Some(429) => Some(ErrorClass::RateLimited),
+            Some(408 | 502 | 504) => Some(ErrorClass::Network),
+            Some(_) => None,
+            None if detail.starts_with("Connection error") || detail.starts_with("Request timed out") => {
+                Some(ErrorClass::Network)
+            }
+            None => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetryPolicy {
+    /// Total number of runs, including the first one
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub backoff_multiplier: f64,
+    pub retryable: Vec<ErrorClass>,
+    /// Switch to `QueryOptions::fallback_model` for every retry
+    pub use_fallback_model: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 2_000,
+            max_backoff_ms: 30_000,
+            backoff_multiplier: 2.0,
+            retryable: vec![
+                ErrorClass::Overloaded,
+                ErrorClass::RateLimited,
+                ErrorClass::Network,
+            ],
+            use_fallback_model: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether a run that failed on `attempt` (1-based) should be retried
+    pub fn should_retry(&self, attempt: u32, class: ErrorClass) -> bool {
+        attempt < self.max_attempts && self.retryable.contains(&class)
+    }
+
+    /// Delay before the run following `attempt` (1-based)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = i32::try_from(attempt.saturating_sub(1)).unwrap_or(i32::MAX);
+        let factor = self.backoff_multiplier.max(1.0).powi(exponent);
+        let delay = (self.initial_backoff_ms as f64 * factor).min(self.max_backoff_ms as f64);
+        Duration::from_millis(delay as u64)
+    }
+}
+
+/// Record of a single CLI run made on behalf of a query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryAttempt {
+    pub attempt: u32,
+    pub model: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error_class: Option<ErrorClass>,
+    pub error: Option<String>,
+    pub retried: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(message: &str) -> ErrorClass {
+        ErrorClass::classify("error_during_execution", Some(message), None)
+    }
+
+    #[test]
+    fn classifies_api_errors_by_status() {
+        assert_eq!(classify(r#"API Error: 529 {"type":"error"}"#), ErrorClass::Overloaded);
+        assert_eq!(classify("API Error: 503 Service Unavailable"), ErrorClass::Overloaded);
+        assert_eq!(classify("API Error: 429 Too Many Requests"), ErrorClass::RateLimited);
+        assert_eq!(classify("API Error: 504 Gateway Timeout"), ErrorClass::Network);
+        assert_eq!(classify("API Error: 400 invalid_request_error"), ErrorClass::Other);
+        assert_eq!(classify("API Error: 401 authentication_error"), ErrorClass::Other);
+    }
+
+    #[test]
+    fn classifies_api_errors_by_type() {
+        let overloaded = r#"API Error: {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert_eq!(classify(overloaded), ErrorClass::Overloaded);
+        let limited = r#"Retrying failed. API Error: {"error":{"type":"rate_limit_error"}}"#;
+        assert_eq!(classify(limited), ErrorClass::RateLimited);
+    }
+
+    #[test]
+    fn classifies_connection_failures() {
+        assert_eq!(classify("API Error: Connection error."), ErrorClass::Network);
+        assert_eq!(classify("API Error: Request timed out."), ErrorClass::Network);
+    }
+
+    #[test]
+    fn ignores_numbers_and_words_outside_api_errors() {
+        assert_eq!(classify("Wrote 529 lines to src/connection.rs"), ErrorClass::Other);
+        assert_eq!(classify("Test timed out after 429ms"), ErrorClass::Other);
+        assert_eq!(classify(""), ErrorClass::Other);
+        assert_eq!(ErrorClass::classify("error_max_turns", None, None), ErrorClass::Other);
+    }
+
+    #[test]
+    fn reads_network_codes_from_stderr_only_without_a_result() {
+        let stderr = Some("Error: read ECONNRESET\n    at TLSWrap.onStreamRead");
+        assert_eq!(ErrorClass::classify(INTERRUPTED_SUBTYPE, None, stderr), ErrorClass::Network);
+        assert_eq!(ErrorClass::classify("error_during_execution", None, stderr), ErrorClass::Other);
+        let mention = Some("grep: ECONNRESET_COUNT: no such variable");
+        assert_eq!(ErrorClass::classify(INTERRUPTED_SUBTYPE, None, mention), ErrorClass::Other);
+    }
+
+    #[test]
+    fn multibyte_text_after_the_prefix_is_not_a_status() {
+        assert_eq!(classify("API Error: é429"), ErrorClass::Other);
+        assert_eq!(classify("API Error: 4é"), ErrorClass::Other);
+        assert_eq!(classify("API Error:"), ErrorClass::Other);
+    }
+
+    #[test]
+    fn retries_retryable_classes_until_the_last_attempt() {
+        let policy = RetryPolicy::default();
+        assert!(policy.should_retry(1, ErrorClass::Overloaded));
+        assert!(policy.should_retry(2, ErrorClass::Network));
+        assert!(!policy.should_retry(3, ErrorClass::Overloaded));
+        assert!(!policy.should_retry(1, ErrorClass::Other));
+    }
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(2_000));
+        assert_eq!(policy.backoff(1), Duration::from_millis(2_000));
+        assert_eq!(policy.backoff(2), Duration::from_millis(4_000));
+        assert_eq!(policy.backoff(4), Duration::from_millis(16_000));
+        assert_eq!(policy.backoff(5), Duration::from_millis(30_000));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(30_000));
+    }
+
+    #[test]
+    fn backoff_never_shrinks() {
+        let policy = RetryPolicy {
+            backoff_multiplier: 0.5,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.backoff(3), Duration::from_millis(2_000));
+    }
+}
@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Where a session is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionState {
+    /// No query has been sent yet
+    Idle,
+    /// Waiting for the session's previous query to finish
+    Queued,
+    /// Preparing and launching the CLI, including relaunches for retries
+    Starting,
+    Running,
+    Completed,
+    Failed,
+    Aborted,
+}
+
+impl SessionState {
+    /// Whether a query is in progress
+    pub fn is_active(self) -> bool {
+        matches!(
+            self,
+            Self::Queued | Self::Starting | Self::Running
+        )
+    }
+}
+
+/// Sent as `session-state-changed` on every transition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStateChanged {
+    pub session_id: String,
+    pub agent_id: String,
+    pub state: SessionState,
+    pub previous: SessionState,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// What a session is doing right now
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub agent_id: String,
+    pub project_id: Option<String>,
+    pub state: SessionState,
+    /// PID of the running CLI process
+    pub pid: Option<u32>,
+    pub working_directory: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// When the current or most recent query started
+    pub started_at: Option<DateTime<Utc>>,
+    pub last_activity: DateTime<Utc>,
+    pub message_count: usize,
+    /// Prompts waiting for the current query to finish
+    pub queued_prompts: usize,
+}
@@ -0,0 +1,87 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, RwLock};
+use tauri::async_runtime::Mutex;
+use tokio::sync::broadcast;
+use tracing::debug;
+
+use super::manager::ClaudeSession;
+use super::state::{SessionState, SessionStateChanged};
+
+const SHARDS: usize = 16;
+/// State changes a slow subscriber may fall behind by before it starts skipping
+const EVENT_CAPACITY: usize = 256;
+
+/// A session behind its own lock
+pub type SessionHandle = Arc<Mutex<ClaudeSession>>;
+
+/// Sessions spread over shards, each behind its own lock. The shard maps are only locked
+/// briefly to look a session up, so a busy session never holds up the others.
+pub struct SessionStore {
+    shards: Vec<RwLock<HashMap<String, SessionHandle>>>,
+    state_changes: broadcast::Sender<SessionStateChanged>,
+}
+
+impl SessionStore {
+    pub fn new() -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| RwLock::new(HashMap::new())).collect(),
+            state_changes: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+
+    fn shard(&self, session_id: &str) -> &RwLock<HashMap<String, SessionHandle>> {
+        let mut hasher = DefaultHasher::new();
+        session_id.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % SHARDS]
+    }
+
+    pub fn insert(&self, session: ClaudeSession) -> SessionHandle {
+        let id = session.id.clone();
+        let handle = Arc::new(Mutex::new(session));
+        self.shard(&id).write().unwrap().insert(id, handle.clone());
+        handle
+    }
+
+    pub fn get(&self, session_id: &str) -> Option<SessionHandle> {
+        self.shard(session_id).read().unwrap().get(session_id).cloned()
+    }
+
+    pub fn remove(&self, session_id: &str) -> Option<SessionHandle> {
+        self.shard(session_id).write().unwrap().remove(session_id)
+    }
+
+    pub fn contains(&self, session_id: &str) -> bool {
+        self.shard(session_id).read().unwrap().contains_key(session_id)
+    }
+
+    /// Every session, in no particular order
+    pub fn all(&self) -> Vec<SessionHandle> {
+        self.shards
+            .iter()
+            .flat_map(|shard| shard.read().unwrap().values().cloned().collect::<Vec<_>>())
+            .collect()
+    }
+
+    /// Run `f` on a session while holding only that session's lock
+    pub async fn update<R>(&self, session_id: &str, f: impl FnOnce(&mut ClaudeSession) -> R) -> Option<R> {
+        let handle = self.get(session_id)?;
+        let mut session = handle.lock().await;
+        Some(f(&mut session))
+    }
+
+    /// Move a session to `state`, announcing the change to subscribers
+    pub async fn transition(&self, session_id: &str, state: SessionState) {
+        let Some(change) = self.update(session_id, |session| session.set_state(state)).await.flatten() else {
+            return;
+        };
+        debug!("Session {} is now {:?} (was {:?})", session_id, change.state, change.previous);
+        // Only fails when nobody is listening
+        let _ = self.state_changes.send(change);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SessionStateChanged> {
+        self.state_changes.subscribe()
+    }
+}
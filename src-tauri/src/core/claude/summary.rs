@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::activity::TurnActivity;
+use super::cli_process::Usage;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub cache_creation_input_tokens: Option<u32>,
+    pub cache_read_input_tokens: Option<u32>,
+}
+
+impl From<&Usage> for TokenUsage {
+    fn from(usage: &Usage) -> Self {
+        Self {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_creation_input_tokens: usage.cache_creation_input_tokens,
+            cache_read_input_tokens: usage.cache_read_input_tokens,
+        }
+    }
+}
+
+/// Everything the CLI reported about a finished query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnSummary {
+    pub session_id: String,
+    pub cli_session_id: Option<String>,
+    pub subtype: String,
+    pub is_error: bool,
+    /// Final text of the turn
+    pub result: Option<String>,
+    pub error: Option<String>,
+    pub num_turns: Option<u32>,
+    pub duration_ms: Option<u64>,
+    pub duration_api_ms: Option<u64>,
+    pub total_cost_usd: Option<f64>,
+    pub usage: Option<TokenUsage>,
+    /// Number of CLI runs it took, including retries
+    pub attempts: u32,
+    /// Files touched and commands run, over all attempts
+    #[serde(default)]
+    pub activity: TurnActivity,
+    /// Snapshot of the worktree from before the turn, to restore with `restore_checkpoint`
+    #[serde(default)]
+    pub checkpoint_id: Option<String>,
+    pub completed_at: DateTime<Utc>,
+}
+
+impl TurnSummary {
+    /// Short human-readable description for the completion message
+    pub fn describe(&self) -> String {
+        let mut details = Vec::new();
+
+        if let Some(turns) = self.num_turns {
+            details.push(format!("{} turn{}", turns, if turns == 1 { "" } else { "s" }));
+        }
+        if let Some(usage) = &self.usage {
+            details.push(format!(
+                "Tokens: {} in, {} out, {} total",
+                usage.input_tokens,
+                usage.output_tokens,
+                usage.input_tokens + usage.output_tokens
+            ));
+        } else if let Some(duration) = self.duration_ms {
+            details.push(format!("{}ms", duration));
+        }
+        let files_changed = self.activity.files_changed().count();
+        if files_changed > 0 {
+            details.push(format!("{} file{} changed", files_changed, if files_changed == 1 { "" } else { "s" }));
+        }
+        if let Some(cost) = self.total_cost_usd {
+            details.push(format!("${:.4}", cost));
+        }
+
+        if details.is_empty() {
+            "Completed successfully".to_string()
+        } else {
+            format!("Completed successfully ({})", details.join(", "))
+        }
+    }
+}
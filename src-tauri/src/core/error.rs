@@ -1,18 +1,98 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fmt;
+use std::io;
+
+/// Stable error identifiers the UI can match on instead of message text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCode {
+    CliNotFound,
+    NotAuthenticated,
+    CliRejectedArguments,
+    RateLimited,
+    CliFailed,
+    SessionNotFound,
+    NotFound,
+    InvalidInput,
+    /// Missing or wrong control API token
+    Unauthorized,
+    NotAGitRepository,
+    GitConflict,
+    DirtyWorktree,
+    GitFailed,
+    Timeout,
+    Network,
+    Io,
+    Internal,
+}
+
+impl ErrorCode {
+    /// Whether trying the same thing again later may succeed
+    pub fn is_retryable(self) -> bool {
+        matches!(self, Self::RateLimited | Self::Timeout | Self::Network)
+    }
+}
+
+/// Error with a code attached, raised from core code through `anyhow`
+#[derive(Debug)]
+pub struct ForgeError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub details: Option<Value>,
+}
+
+impl ForgeError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: impl Serialize) -> Self {
+        self.details = serde_json::to_value(details).ok();
+        self
+    }
+
+    pub fn session_not_found(session_id: &str) -> Self {
+        Self::new(ErrorCode::SessionNotFound, "Session not found")
+            .with_details(serde_json::json!({ "sessionId": session_id }))
+    }
+}
+
+impl fmt::Display for ForgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ForgeError {}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ErrorResponse {
     pub error: String,
+    pub code: ErrorCode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+    pub retryable: bool,
 }
 
 impl ErrorResponse {
-    pub fn new(error: impl Into<String>) -> Self {
+    pub fn new(code: ErrorCode, error: impl Into<String>) -> Self {
         Self {
             error: error.into(),
+            code,
+            details: None,
+            retryable: code.is_retryable(),
         }
     }
+
+    pub fn with_details(mut self, details: impl Serialize) -> Self {
+        self.details = serde_json::to_value(details).ok();
+        self
+    }
 }
 
 impl fmt::Display for ErrorResponse {
@@ -23,22 +103,50 @@ impl fmt::Display for ErrorResponse {
 
 impl std::error::Error for ErrorResponse {}
 
-impl From<anyhow::Error> for ErrorResponse {
-    fn from(err: anyhow::Error) -> Self {
-        Self::new(err.to_string())
+impl From<ForgeError> for ErrorResponse {
+    fn from(err: ForgeError) -> Self {
+        Self {
+            details: err.details,
+            ..Self::new(err.code, err.message)
+        }
     }
 }
 
-impl From<String> for ErrorResponse {
-    fn from(err: String) -> Self {
-        Self::new(err)
-    }
-}
+impl From<anyhow::Error> for ErrorResponse {
+    fn from(err: anyhow::Error) -> Self {
+        let message = err.to_string();
+        let mut details = None;
+
+        // Code by the outermost cause whose type says what went wrong
+        let code = err
+            .chain()
+            .find_map(|cause| {
+                if let Some(forge) = cause.downcast_ref::<ForgeError>() {
+                    details = forge.details.clone();
+                    Some(forge.code)
+                } else if let Some(io) = cause.downcast_ref::<io::Error>() {
+                    Some(match io.kind() {
+                        io::ErrorKind::NotFound => ErrorCode::NotFound,
+                        io::ErrorKind::TimedOut => ErrorCode::Timeout,
+                        _ => ErrorCode::Io,
+                    })
+                } else if cause.is::<tokio::time::error::Elapsed>() {
+                    Some(ErrorCode::Timeout)
+                } else if let Some(http) = cause.downcast_ref::<reqwest::Error>() {
+                    Some(if http.is_timeout() { ErrorCode::Timeout } else { ErrorCode::Network })
+                } else if cause.is::<serde_json::Error>() {
+                    Some(ErrorCode::InvalidInput)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(ErrorCode::Internal);
 
-impl From<&str> for ErrorResponse {
-    fn from(err: &str) -> Self {
-        Self::new(err)
+        Self {
+            details,
+            ..Self::new(code, message)
+        }
     }
 }
 
-pub type Result<T> = std::result::Result<T, ErrorResponse>;
\ No newline at end of file
+pub type Result<T> = std::result::Result<T, ErrorResponse>;
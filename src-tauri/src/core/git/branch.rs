@@ -1,10 +1,12 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use std::process::Command;
 use tracing::{error, info};
 
+use super::command::{failure, GitCommand};
 use super::types::GitCheckoutOptions;
 
 pub fn create(directory: &str, branch_name: &str, checkout: bool) -> Result<()> {
+    let _span = crate::span!("git", op = "create_branch", directory = %directory).entered();
     info!("Creating branch {} in {}", branch_name, directory);
 
     // Create branch
@@ -12,12 +14,12 @@ pub fn create(directory: &str, branch_name: &str, checkout: bool) -> Result<()>
         .arg("branch")
         .arg(branch_name)
         .current_dir(directory)
-        .output()?;
+        .run()?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         error!("Failed to create branch: {}", stderr);
-        return Err(anyhow!("Failed to create branch: {}", stderr));
+        return Err(failure("Failed to create branch", &stderr));
     }
 
     // Checkout if requested
@@ -30,6 +32,7 @@ pub fn create(directory: &str, branch_name: &str, checkout: bool) -> Result<()>
 }
 
 pub fn checkout(directory: &str, branch: &str, options: Option<GitCheckoutOptions>) -> Result<()> {
+    let _span = crate::span!("git", op = "checkout", directory = %directory).entered();
     let opts = options.unwrap_or_default();
     
     info!("Checking out branch {} in {}", branch, directory);
@@ -62,13 +65,29 @@ fn checkout_branch_with_options(directory: &str, branch: &str, force: bool) -> R
     cmd.arg(branch);
     cmd.current_dir(directory);
 
-    let output = cmd.output()?;
+    let output = cmd.run()?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         error!("Failed to checkout branch: {}", stderr);
-        return Err(anyhow!("Failed to checkout branch: {}", stderr));
+        return Err(failure("Failed to checkout branch", &stderr));
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+/// Branch the remote's HEAD points at, falling back to the checked-out branch
+pub fn default_branch(directory: &str) -> Option<String> {
+    let symbolic_ref = |name: &str| {
+        let output = Command::new("git")
+            .args(["symbolic-ref", "--quiet", "--short", name])
+            .current_dir(directory)
+            .run()
+            .ok()?;
+        let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !branch.is_empty()).then_some(branch)
+    };
+
+    symbolic_ref("refs/remotes/origin/HEAD")
+        .map(|remote| remote.trim_start_matches("origin/").to_string())
+        .or_else(|| symbolic_ref("HEAD"))
+}
@@ -0,0 +1,270 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::path::Path;
+use std::process::Command;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use super::command::{failure, GitCommand};
+use super::paths::toplevel;
+use super::types::Checkpoint;
+use crate::core::error::{ErrorCode, ForgeError};
+
+/// Hidden refs holding the snapshots; `git log --all` shows them, branches and tags don't
+const CHECKPOINT_REFS: &str = "refs/forge/checkpoints";
+/// Oldest checkpoints beyond this many per repository are dropped
+const MAX_CHECKPOINTS: usize = 200;
+
+/// Snapshot the worktree containing `directory`, untracked files included and ignored files
+/// left out, like `git stash --include-untracked` but without touching the worktree.
+/// Returns `None` outside a repository.
+pub fn create(
+    directory: &str,
+    label: &str,
+    session_id: Option<&str>,
+    message_seq: Option<u64>,
+) -> Result<Option<Checkpoint>> {
+    let _span = crate::span!("git", op = "checkpoint", directory = %directory).entered();
+    let Some(root) = toplevel(directory) else {
+        debug!("{} is not in a git repository, skipping checkpoint", directory);
+        return Ok(None);
+    };
+
+    let head = git(&root, &["rev-parse", "--verify", "--quiet", "HEAD"], None).ok();
+
+    // The staged state, so a restore can bring it back too
+    let index_tree = git(&root, &["write-tree"], None)?;
+    let mut args = vec!["commit-tree", index_tree.as_str(), "-m", "index"];
+    if let Some(head) = &head {
+        args.extend(["-p", head.as_str()]);
+    }
+    let index_commit = git(&root, &args, None)?;
+
+    // Everything else goes through a scratch index seeded from the real one, so only
+    // files that changed since the last `git add` get hashed
+    let scratch = std::env::temp_dir().join(format!("forge-checkpoint-{}.index", Uuid::new_v4().simple()));
+    let real_index = git(&root, &["rev-parse", "--git-path", "index"], None)?;
+    let real_index = Path::new(&root).join(real_index);
+    if real_index.exists() {
+        std::fs::copy(&real_index, &scratch)?;
+    }
+    let tree = git(&root, &["add", "--all"], Some(&scratch))
+        .and_then(|_| git(&root, &["write-tree"], Some(&scratch)));
+    let _ = std::fs::remove_file(&scratch);
+    let tree = tree?;
+
+    let mut message = format!("{}\n", label);
+    if session_id.is_some() || message_seq.is_some() {
+        message.push('\n');
+    }
+    if let Some(session_id) = session_id {
+        message.push_str(&format!("Forge-Session: {}\n", session_id));
+    }
+    if let Some(seq) = message_seq {
+        message.push_str(&format!("Forge-Message-Seq: {}\n", seq));
+    }
+    let mut args = vec!["commit-tree", tree.as_str(), "-m", message.as_str()];
+    if let Some(head) = &head {
+        args.extend(["-p", head.as_str()]);
+    }
+    args.extend(["-p", index_commit.as_str()]);
+    let commit = git(&root, &args, None)?;
+
+    let created_at = Utc::now();
+    let id = format!("{}-{}", created_at.timestamp_millis(), &Uuid::new_v4().simple().to_string()[..8]);
+    git(&root, &["update-ref", &format!("{}/{}", CHECKPOINT_REFS, id), &commit], None)?;
+    info!("Created checkpoint {} in {}", id, root);
+
+    for old in list(&root)?.into_iter().skip(MAX_CHECKPOINTS) {
+        git(&root, &["update-ref", "-d", &format!("{}/{}", CHECKPOINT_REFS, old.id)], None)?;
+        debug!("Dropped checkpoint {}", old.id);
+    }
+
+    Ok(Some(Checkpoint {
+        id,
+        commit,
+        head,
+        session_id: session_id.map(str::to_string),
+        message_seq,
+        label: label.to_string(),
+        created_at,
+    }))
+}
+
+/// Checkpoints of the repository containing `directory`, newest first
+pub fn list(directory: &str) -> Result<Vec<Checkpoint>> {
+    let output = git(
+        directory,
+        &[
+            "for-each-ref",
+            "--sort=-refname",
+            "--format=%(refname:lstrip=3)%00%(objectname)%00%(parent)%00%(contents)%00",
+            CHECKPOINT_REFS,
+        ],
+        None,
+    )?;
+
+    Ok(parse_checkpoints(&output))
+}
+
+/// Put the worktree and index back the way they were when the checkpoint was taken. Files
+/// created since are deleted; commits made since are kept. The state being replaced is saved
+/// as a new checkpoint first, which is returned so the restore can be undone.
+pub fn restore(directory: &str, id: &str) -> Result<Checkpoint> {
+    let _span = crate::span!("git", op = "restore_checkpoint", directory = %directory).entered();
+    let root = toplevel(directory).ok_or_else(|| {
+        ForgeError::new(ErrorCode::NotAGitRepository, format!("{} is not in a git repository", directory))
+    })?;
+    let target = list(&root)?
+        .into_iter()
+        .find(|checkpoint| checkpoint.id == id)
+        .ok_or_else(|| {
+            ForgeError::new(ErrorCode::NotFound, format!("Checkpoint not found: {}", id))
+                .with_details(serde_json::json!({ "checkpointId": id, "directory": directory }))
+        })?;
+
+    let backup = create(&root, &format!("Before restoring checkpoint {}", id), None, None)?.ok_or_else(|| {
+        ForgeError::new(ErrorCode::NotAGitRepository, format!("{} is not in a git repository", root))
+    })?;
+
+    // With the index matching the worktree, switching trees also removes files added since
+    git(&root, &["read-tree", &format!("{}^{{tree}}", backup.commit)], None)?;
+    git(&root, &["read-tree", "--reset", "-u", &format!("{}^{{tree}}", target.commit)], None)?;
+    // The staged state is the snapshot's last parent, after HEAD if there was one
+    let index_parent = if target.head.is_some() { 2 } else { 1 };
+    let index_commit = git(&root, &["rev-parse", &format!("{}^{}", target.commit, index_parent)], None)?;
+    git(&root, &["read-tree", &format!("{}^{{tree}}", index_commit)], None)?;
+    // Exits non-zero when files differ from the index, which is expected here
+    let _ = Command::new("git").args(["update-index", "-q", "--refresh"]).current_dir(&root).run();
+
+    info!("Restored checkpoint {} in {} (previous state saved as {})", id, root, backup.id);
+    Ok(backup)
+}
+
+/// Checkpoints from `for-each-ref` output, one NUL-terminated record per line
+fn parse_checkpoints(output: &str) -> Vec<Checkpoint> {
+    output
+        .split("\0\n")
+        .filter(|record| !record.trim().is_empty())
+        .filter_map(parse_checkpoint)
+        .collect()
+}
+
+fn parse_checkpoint(record: &str) -> Option<Checkpoint> {
+    let mut fields = record.trim_start_matches('\n').splitn(4, '\0');
+    let id = fields.next()?.to_string();
+    let commit = fields.next()?.to_string();
+    let parents: Vec<&str> = fields.next()?.split_whitespace().collect();
+    let message = fields.next().unwrap_or_default();
+
+    let millis = id.split('-').next()?.parse().ok()?;
+    let trailer = |key: &str| {
+        message
+            .lines()
+            .find_map(|line| line.strip_prefix(key))
+            .map(|value| value.trim().to_string())
+    };
+
+    Some(Checkpoint {
+        head: (parents.len() == 2).then(|| parents[0].to_string()),
+        session_id: trailer("Forge-Session:"),
+        message_seq: trailer("Forge-Message-Seq:").and_then(|seq| seq.parse().ok()),
+        label: message.lines().next().unwrap_or_default().to_string(),
+        created_at: DateTime::from_timestamp_millis(millis)?,
+        id,
+        commit,
+    })
+}
+
+/// Run git in `directory`, optionally against another index file, returning trimmed stdout
+fn git(directory: &str, args: &[&str], index_file: Option<&Path>) -> Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.args(args)
+        .current_dir(directory)
+        // Checkpoints are made on the user's behalf even where no git identity is set up
+        .env("GIT_AUTHOR_NAME", "Forge")
+        .env("GIT_AUTHOR_EMAIL", "forge@localhost")
+        .env("GIT_COMMITTER_NAME", "Forge")
+        .env("GIT_COMMITTER_EMAIL", "forge@localhost");
+    if let Some(index_file) = index_file {
+        cmd.env("GIT_INDEX_FILE", index_file);
+    }
+
+    let output = cmd.run()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(failure(&format!("git {} failed", args[0]), &stderr));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMIT: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const HEAD: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+    const INDEX: &str = "cccccccccccccccccccccccccccccccccccccccc";
+
+    #[test]
+    fn record_with_head_and_trailers() {
+        let record = format!(
+            "1760000000000-1a2b3c4d\0{COMMIT}\0{HEAD} {INDEX}\0\
+             Fix the build\n\nForge-Session: agent-1\nForge-Message-Seq: 7\n"
+        );
+        let checkpoint = parse_checkpoint(&record).unwrap();
+
+        assert_eq!(checkpoint.id, "1760000000000-1a2b3c4d");
+        assert_eq!(checkpoint.commit, COMMIT);
+        assert_eq!(checkpoint.head.as_deref(), Some(HEAD));
+        assert_eq!(checkpoint.label, "Fix the build");
+        assert_eq!(checkpoint.session_id.as_deref(), Some("agent-1"));
+        assert_eq!(checkpoint.message_seq, Some(7));
+        assert_eq!(checkpoint.created_at.timestamp_millis(), 1_760_000_000_000);
+    }
+
+    #[test]
+    fn record_without_head_or_trailers() {
+        // Taken before the first commit, so the index commit is the only parent
+        let record = format!("1760000000000-1a2b3c4d\0{COMMIT}\0{INDEX}\0Before restoring checkpoint x\n");
+        let checkpoint = parse_checkpoint(&record).unwrap();
+
+        assert_eq!(checkpoint.head, None);
+        assert_eq!(checkpoint.session_id, None);
+        assert_eq!(checkpoint.message_seq, None);
+        assert_eq!(checkpoint.label, "Before restoring checkpoint x");
+    }
+
+    #[test]
+    fn malformed_records_are_skipped() {
+        assert!(parse_checkpoint("").is_none());
+        assert!(parse_checkpoint("1760000000000-1a2b3c4d").is_none());
+        assert!(parse_checkpoint(&format!("not-a-time\0{COMMIT}\0{INDEX}\0label")).is_none());
+    }
+
+    #[test]
+    fn empty_output_has_no_checkpoints() {
+        assert!(parse_checkpoints("").is_empty());
+        assert!(parse_checkpoints("\n").is_empty());
+    }
+
+    #[test]
+    fn records_are_split_on_nul_and_newline() {
+        let output = format!(
+            "1760000000002-00000002\0{COMMIT}\0{HEAD} {INDEX}\0Second\n\nline two\n\0\n\
+             1760000000001-00000001\0{HEAD}\0{INDEX}\0First\n\0\n"
+        );
+        let checkpoints = parse_checkpoints(&output);
+
+        let ids: Vec<&str> = checkpoints.iter().map(|checkpoint| checkpoint.id.as_str()).collect();
+        assert_eq!(ids, ["1760000000002-00000002", "1760000000001-00000001"]);
+        assert_eq!(checkpoints[0].label, "Second");
+        assert_eq!(checkpoints[1].commit, HEAD);
+    }
+
+    #[test]
+    fn multibyte_labels_are_kept() {
+        let record = format!("1760000000000-1a2b3c4d\0{COMMIT}\0{INDEX}\0Réparer le café ☕ 修正\n");
+        assert_eq!(parse_checkpoint(&record).unwrap().label, "Réparer le café ☕ 修正");
+    }
+}
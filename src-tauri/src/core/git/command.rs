@@ -0,0 +1,46 @@
+use std::io;
+use std::process::{Command, Output, Stdio};
+use tracing::debug;
+
+use crate::core::error::{ErrorCode, ForgeError};
+
+/// Like `Command::output`, but logs the git invocation in a span carrying its PID
+pub trait GitCommand {
+    fn run(&mut self) -> io::Result<Output>;
+}
+
+impl GitCommand for Command {
+    fn run(&mut self) -> io::Result<Output> {
+        let child = self
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let _span = crate::span!("git_command", pid = child.id()).entered();
+        debug!("Running {:?}", self);
+        child.wait_with_output()
+    }
+}
+
+/// Error for a failed git invocation, coded by what its stderr says went wrong
+pub(super) fn failure(action: &str, stderr: &str) -> anyhow::Error {
+    let lower = stderr.to_lowercase();
+    let code = if lower.contains("not a git repository") {
+        ErrorCode::NotAGitRepository
+    } else if lower.contains("conflict") || lower.contains("needs merge") || lower.contains("unmerged") {
+        ErrorCode::GitConflict
+    } else if lower.contains("would be overwritten")
+        || lower.contains("commit your changes or stash them")
+        || lower.contains("uncommitted changes")
+        || lower.contains("you have unstaged changes")
+    {
+        ErrorCode::DirtyWorktree
+    } else {
+        ErrorCode::GitFailed
+    };
+
+    ForgeError::new(code, format!("{}: {}", action, stderr.trim()))
+        .with_details(serde_json::json!({ "stderr": stderr }))
+        .into()
+}
@@ -0,0 +1,395 @@
+use anyhow::Result;
+use std::fmt::Write as _;
+use std::process::Command;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::command::{failure, GitCommand};
+use super::types::{ConflictedFile, MergeMethod, MergeOptions, MergeReport};
+use super::worktree::checked_out_at;
+use crate::core::error::{ErrorCode, ForgeError};
+
+/// Versions larger than this are left out of conflict reports
+const MAX_VERSION_BYTES: u64 = 256 * 1024;
+/// Each version quoted in a resolution prompt is cut to this many characters
+const MAX_PROMPT_VERSION_CHARS: usize = 4000;
+
+/// Trial-merge `source` into `target` without touching any worktree, index or ref, reporting
+/// the files that would conflict. Needs git 2.38 or later.
+pub fn check(directory: &str, source: &str, target: &str) -> Result<MergeReport> {
+    let _span = crate::span!("git", op = "check_merge", directory = %directory).entered();
+    Ok(trial_merge(directory, source, target)?.0)
+}
+
+/// Merge the `source` branch into the `target` branch. Nothing changes when the trial merge
+/// conflicts; the report lists the conflicts instead. The merge is done without a worktree,
+/// except that a target checked out somewhere is fast-forwarded there to keep it in step.
+pub fn merge(directory: &str, source: &str, target: &str, options: MergeOptions) -> Result<MergeReport> {
+    let _span = crate::span!("git", op = "merge", directory = %directory).entered();
+    info!("Merging {} into {} in {} ({:?})", source, target, directory, options.method);
+    require_branch(directory, target)?;
+    if options.method == MergeMethod::Rebase {
+        require_branch(directory, source)?;
+    }
+
+    let (mut report, tree) = trial_merge(directory, source, target)?;
+    if !report.conflicts.is_empty() {
+        info!("Merging {} into {} conflicts in {} files", source, target, report.conflicts.len());
+        return Ok(report);
+    }
+    if report.commits_ahead == 0 {
+        info!("{} is already merged into {}", source, target);
+        report.merged_commit = Some(report.target_commit.clone());
+        return Ok(report);
+    }
+
+    let fast_forward = report.commits_behind == 0;
+    let merged = match options.method {
+        MergeMethod::Merge if fast_forward && !options.no_fast_forward => report.source_commit.clone(),
+        MergeMethod::Merge => {
+            let message = options
+                .message
+                .unwrap_or_else(|| format!("Merge branch '{}' into {}", source, target));
+            git(
+                directory,
+                &["commit-tree", &tree, "-p", &report.target_commit, "-p", &report.source_commit, "-m", &message],
+            )?
+        }
+        MergeMethod::Rebase if fast_forward => report.source_commit.clone(),
+        MergeMethod::Rebase => rebase(directory, source, &report)?,
+    };
+
+    advance(directory, target, &report.target_commit, &merged)?;
+    info!("Merged {} into {} at {}", source, target, merged);
+    report.merged_commit = Some(merged);
+    Ok(report)
+}
+
+/// Prompt asking an agent working on `source` to bring in `target` and resolve the conflicts,
+/// so that `source` merges cleanly afterwards
+pub fn resolution_prompt(report: &MergeReport, method: MergeMethod) -> String {
+    let (command, done) = match method {
+        MergeMethod::Merge => (format!("git merge {}", report.target), "commit the merge"),
+        MergeMethod::Rebase => (format!("git rebase {}", report.target), "continue the rebase until it finishes"),
+    };
+    let mut prompt = format!(
+        "Merging `{}` into `{}` conflicts in {} file{}. Run `{}` in this worktree, resolve the conflicts \
+         keeping the intent of both sides, and {}. Don't change anything else.\n",
+        report.source,
+        report.target,
+        report.conflicts.len(),
+        if report.conflicts.len() == 1 { "" } else { "s" },
+        command,
+        done
+    );
+
+    for file in &report.conflicts {
+        let _ = write!(prompt, "\n## {}\n\n", file.path);
+        for message in &file.messages {
+            let _ = writeln!(prompt, "{}", message);
+        }
+        for (branch, version) in [(&report.target, &file.target), (&report.source, &file.source)] {
+            let Some(version) = version else { continue };
+            let mut shown: String = version.chars().take(MAX_PROMPT_VERSION_CHARS).collect();
+            if shown.len() < version.len() {
+                shown.push_str("\n[truncated]");
+            }
+            let _ = write!(prompt, "\nVersion on `{}`:\n```\n{}\n```\n", branch, shown.trim_end());
+        }
+    }
+    prompt
+}
+
+/// The report for merging `source` into `target`, with the tree the merge produces
+fn trial_merge(directory: &str, source: &str, target: &str) -> Result<(MergeReport, String)> {
+    let source_commit = resolve(directory, source)?;
+    let target_commit = resolve(directory, target)?;
+    let merge_base = git(directory, &["merge-base", &target_commit, &source_commit]).ok();
+
+    let range = format!("{}...{}", target_commit, source_commit);
+    let counts = git(directory, &["rev-list", "--left-right", "--count", &range])?;
+    let mut counts = counts.split_whitespace().map(|count| count.parse().unwrap_or(0));
+    let commits_behind = counts.next().unwrap_or(0);
+    let commits_ahead = counts.next().unwrap_or(0);
+
+    // Stage 2 is the target's side and stage 3 the source's
+    let output = Command::new("git")
+        .args(["merge-tree", "--write-tree", "-z", &target_commit, &source_commit])
+        .current_dir(directory)
+        .run()?;
+    // Exit status 1 means the merge has conflicts
+    if !matches!(output.status.code(), Some(0) | Some(1)) {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(failure("Failed to trial-merge", &stderr));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (tree, mut conflicts) = parse_merge_tree(&stdout, |object| read_blob(directory, object));
+    // Git names the sides by the commits it was given
+    for message in conflicts.iter_mut().flat_map(|file| file.messages.iter_mut()) {
+        *message = message.replace(&target_commit, target).replace(&source_commit, source);
+    }
+
+    let report = MergeReport {
+        source: source.to_string(),
+        target: target.to_string(),
+        source_commit,
+        target_commit,
+        merge_base,
+        commits_ahead,
+        commits_behind,
+        conflicts,
+        merged_commit: None,
+    };
+    Ok((report, tree))
+}
+
+/// Split `merge-tree -z` output into the merged tree and the conflicted files. The output is the
+/// tree, one `<mode> <object> <stage>\t<path>` entry per conflicted stage, an empty entry, then
+/// messages as `<path count>, <paths...>, <type>, <text>`. Versions are read with `read_blob`.
+fn parse_merge_tree(output: &str, read_blob: impl Fn(&str) -> Option<String>) -> (String, Vec<ConflictedFile>) {
+    let (entries, messages) = output.split_once("\0\0").unwrap_or((output, ""));
+    let mut entries = entries.split('\0');
+    let tree = entries.next().unwrap_or_default().to_string();
+
+    let mut conflicts: Vec<ConflictedFile> = Vec::new();
+    for entry in entries {
+        let Some((info, path)) = entry.split_once('\t') else {
+            continue;
+        };
+        let mut fields = info.split(' ');
+        let (Some(mode), Some(object), Some(stage)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        // Submodules have no contents to show
+        let version = if mode == "160000" { None } else { read_blob(object) };
+        let file = conflicted_file(&mut conflicts, path);
+        match stage {
+            "1" => file.base = version,
+            "2" => file.target = version,
+            "3" => file.source = version,
+            _ => {}
+        }
+    }
+
+    let mut fields = messages.split('\0');
+    while let Some(count) = fields.next().and_then(|count| count.parse::<usize>().ok()) {
+        let paths: Vec<&str> = fields.by_ref().take(count).collect();
+        let (Some(kind), Some(text)) = (fields.next(), fields.next()) else {
+            break;
+        };
+        // The rest, like "Auto-merging", is informational
+        if !kind.starts_with("CONFLICT") {
+            continue;
+        }
+        for path in paths {
+            conflicted_file(&mut conflicts, path).messages.push(text.trim().to_string());
+        }
+    }
+
+    (tree, conflicts)
+}
+
+fn conflicted_file<'a>(conflicts: &'a mut Vec<ConflictedFile>, path: &str) -> &'a mut ConflictedFile {
+    let index = match conflicts.iter().position(|file| file.path == path) {
+        Some(index) => index,
+        None => {
+            conflicts.push(ConflictedFile {
+                path: path.to_string(),
+                messages: Vec::new(),
+                base: None,
+                target: None,
+                source: None,
+            });
+            conflicts.len() - 1
+        }
+    };
+    &mut conflicts[index]
+}
+
+/// Contents of a blob, unless it is binary or too large
+fn read_blob(directory: &str, object: &str) -> Option<String> {
+    let size: u64 = git(directory, &["cat-file", "-s", object]).ok()?.parse().ok()?;
+    if size > MAX_VERSION_BYTES {
+        return None;
+    }
+    let output = Command::new("git")
+        .args(["cat-file", "blob", object])
+        .current_dir(directory)
+        .run()
+        .ok()?;
+    if !output.status.success() || output.stdout.contains(&0) {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Replay the source's commits onto the target, in the worktree it is checked out in or in a
+/// temporary one. Returns the rebased tip, which the source branch now points at.
+fn rebase(directory: &str, source: &str, report: &MergeReport) -> Result<String> {
+    let (worktree, temporary) = match checked_out_at(directory, source)? {
+        Some(worktree) => (worktree, false),
+        None => {
+            let path = std::env::temp_dir().join(format!("forge-rebase-{}", Uuid::new_v4().simple()));
+            let path = path.to_string_lossy().into_owned();
+            git(directory, &["worktree", "add", "--detach", &path, &report.source_commit])?;
+            (path, true)
+        }
+    };
+
+    let rebased = git(&worktree, &["rebase", &report.target_commit])
+        .and_then(|_| git(&worktree, &["rev-parse", "HEAD"]));
+    if rebased.is_err() {
+        let _ = git(&worktree, &["rebase", "--abort"]);
+    }
+    if temporary {
+        if let Err(e) = git(directory, &["worktree", "remove", "--force", &worktree]) {
+            warn!("Failed to remove temporary worktree {}: {}", worktree, e);
+        }
+    }
+    let rebased = rebased?;
+
+    if temporary {
+        let branch = format!("refs/heads/{}", source);
+        git(directory, &["update-ref", &branch, &rebased, &report.source_commit])?;
+    }
+    Ok(rebased)
+}
+
+/// Move the target branch from `from` to `to`. Where the branch is checked out this is a
+/// fast-forward, which carries local changes along and refuses to overwrite them.
+fn advance(directory: &str, target: &str, from: &str, to: &str) -> Result<()> {
+    match checked_out_at(directory, target)? {
+        Some(worktree) => git(&worktree, &["merge", "--ff-only", "--quiet", to])?,
+        None => git(directory, &["update-ref", &format!("refs/heads/{}", target), to, from])?,
+    };
+    Ok(())
+}
+
+fn resolve(directory: &str, revision: &str) -> Result<String> {
+    git(directory, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", revision)]).map_err(|_| {
+        ForgeError::new(ErrorCode::NotFound, format!("Branch not found: {}", revision))
+            .with_details(serde_json::json!({ "branch": revision, "directory": directory }))
+            .into()
+    })
+}
+
+fn require_branch(directory: &str, branch: &str) -> Result<()> {
+    git(directory, &["show-ref", "--verify", "--quiet", &format!("refs/heads/{}", branch)])
+        .map(drop)
+        .map_err(|_| {
+            ForgeError::new(ErrorCode::InvalidInput, format!("{} is not a local branch", branch))
+                .with_details(serde_json::json!({ "branch": branch, "directory": directory }))
+                .into()
+        })
+}
+
+/// Run git in `directory`, returning trimmed stdout
+fn git(directory: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new("git").args(args).current_dir(directory).run()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(failure(&format!("git {} failed", args[0]), &stderr));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "1111111111111111111111111111111111111111";
+    const TARGET: &str = "2222222222222222222222222222222222222222";
+    const SOURCE: &str = "3333333333333333333333333333333333333333";
+
+    fn read(object: &str) -> Option<String> {
+        match object {
+            BASE => Some("base\n".to_string()),
+            TARGET => Some("target\n".to_string()),
+            SOURCE => Some("source\n".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn empty_output_has_no_tree_or_conflicts() {
+        let (tree, conflicts) = parse_merge_tree("", read);
+        assert_eq!(tree, "");
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn clean_merge_is_just_the_tree() {
+        let (tree, conflicts) = parse_merge_tree("d1b7150265971806e3f333adde51e2172409d421\0", read);
+        assert_eq!(tree, "d1b7150265971806e3f333adde51e2172409d421");
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn conflicts_collect_stages_and_messages() {
+        let output = format!(
+            "cc3a14a96fc12c4b1e10f4ded0ae0510d00b8256\0\
+             100644 {BASE} 1\tf.txt\0100644 {TARGET} 2\tf.txt\0100644 {SOURCE} 3\tf.txt\0\
+             100644 {BASE} 1\tg.txt\0100644 {TARGET} 2\tg.txt\0\0\
+             1\0f.txt\0Auto-merging\0Auto-merging f.txt\n\0\
+             1\0f.txt\0CONFLICT (contents)\0CONFLICT (content): Merge conflict in f.txt\n\0\
+             1\0g.txt\0CONFLICT (modify/delete)\0CONFLICT (modify/delete): g.txt deleted in source\n\0"
+        );
+        let (tree, conflicts) = parse_merge_tree(&output, read);
+
+        assert_eq!(tree, "cc3a14a96fc12c4b1e10f4ded0ae0510d00b8256");
+        assert_eq!(conflicts.len(), 2);
+        let f = &conflicts[0];
+        assert_eq!(f.path, "f.txt");
+        assert_eq!(f.base.as_deref(), Some("base\n"));
+        assert_eq!(f.target.as_deref(), Some("target\n"));
+        assert_eq!(f.source.as_deref(), Some("source\n"));
+        assert_eq!(f.messages, ["CONFLICT (content): Merge conflict in f.txt"]);
+        let g = &conflicts[1];
+        assert_eq!(g.path, "g.txt");
+        assert_eq!(g.source, None);
+        assert_eq!(g.messages, ["CONFLICT (modify/delete): g.txt deleted in source"]);
+    }
+
+    #[test]
+    fn messages_can_name_several_paths() {
+        let output = "tree\0\0\
+                      2\0old.rs\0new.rs\0CONFLICT (rename/delete)\0CONFLICT (rename/delete): old.rs renamed to new.rs\0";
+        let (_, conflicts) = parse_merge_tree(output, read);
+
+        let paths: Vec<&str> = conflicts.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, ["old.rs", "new.rs"]);
+        assert!(conflicts.iter().all(|file| file.messages.len() == 1));
+    }
+
+    #[test]
+    fn submodules_and_unreadable_blobs_have_no_contents() {
+        let output = format!("tree\0160000 {TARGET} 2\tvendor/lib\0100644 {} 3\tbig.bin\0\0", "f".repeat(40));
+        let (_, conflicts) = parse_merge_tree(&output, read);
+
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].target, None);
+        assert_eq!(conflicts[1].source, None);
+    }
+
+    #[test]
+    fn multibyte_paths_and_messages_are_kept() {
+        let output = format!(
+            "tree\0100644 {TARGET} 2\tdocs/résumé über.md\0\0\
+             1\0docs/résumé über.md\0CONFLICT (contents)\0CONFLICT (content): Merge conflict in docs/résumé über.md — 日本語\0"
+        );
+        let (_, conflicts) = parse_merge_tree(&output, read);
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].path, "docs/résumé über.md");
+        assert_eq!(conflicts[0].target.as_deref(), Some("target\n"));
+        assert_eq!(conflicts[0].messages, ["CONFLICT (content): Merge conflict in docs/résumé über.md — 日本語"]);
+    }
+
+    #[test]
+    fn truncated_messages_are_ignored() {
+        let output = "tree\0\0\
+                      1\0f.txt\0CONFLICT (contents)";
+        let (_, conflicts) = parse_merge_tree(output, read);
+        assert!(conflicts.is_empty());
+    }
+}
@@ -1,9 +1,23 @@
 pub mod branch;
+pub mod checkpoint;
+mod command;
+pub mod merge;
+pub mod paths;
 pub mod stash;
 pub mod status;
 pub mod types;
+pub mod worktree;
 
 pub use status::check_status;
-pub use branch::{create as create_branch, checkout as checkout_branch};
+pub use paths::{git_dirs, ignored as ignored_paths, ignored_directories, toplevel};
+pub use branch::{create as create_branch, checkout as checkout_branch, default_branch};
+pub use checkpoint::{create as create_checkpoint, list as list_checkpoints, restore as restore_checkpoint};
+pub use merge::{check as check_merge, merge as merge_branch, resolution_prompt};
 pub use stash::stash as stash_changes;
-pub use types::{GitStatus, GitCheckoutOptions, StashOptions};
\ No newline at end of file
+pub use types::{
+    Checkpoint, ConflictedFile, GitCheckoutOptions, GitStatus, MergeMethod, MergeOptions, MergeReport,
+    StashOptions,
+};
+pub use worktree::{
+    add as add_worktree, checked_out_at, checkout as checkout_worktree, remove as remove_worktree,
+};
\ No newline at end of file
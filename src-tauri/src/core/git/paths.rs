@@ -0,0 +1,90 @@
+use anyhow::Result;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+use super::command::{failure, GitCommand};
+
+/// Top directory of the worktree containing `directory`, or `None` outside a repository
+pub fn toplevel(directory: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(directory)
+        .run()
+        .ok()?;
+    let toplevel = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !toplevel.is_empty()).then_some(toplevel)
+}
+
+/// The git directory of the worktree containing `directory`, and the one its repository's
+/// worktrees share for refs and objects. They differ in linked worktrees, where `.git` is a file.
+pub fn git_dirs(directory: &str) -> Option<(PathBuf, PathBuf)> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--path-format=absolute", "--git-dir", "--git-common-dir"])
+        .current_dir(directory)
+        .run()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines().map(|line| PathBuf::from(line.trim()));
+    Some((lines.next()?, lines.next()?))
+}
+
+/// Untracked directories the repository at `directory` ignores, like `node_modules`, as
+/// absolute paths. Directories inside them aren't listed.
+pub fn ignored_directories(directory: &str) -> Result<HashSet<PathBuf>> {
+    let output = Command::new("git")
+        .args(["ls-files", "-z", "--others", "--ignored", "--exclude-standard", "--directory"])
+        .current_dir(directory)
+        .run()?;
+    if !output.status.success() {
+        return Err(failure("Failed to list ignored directories", &String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(output
+        .stdout
+        .split(|&byte| byte == 0)
+        .filter_map(|path| String::from_utf8_lossy(path).strip_suffix('/').map(str::to_string))
+        .map(|path| Path::new(directory).join(path))
+        .collect())
+}
+
+/// Which of `paths` the repository at `directory` ignores. Tracked files are never ignored.
+pub fn ignored(directory: &str, paths: &[PathBuf]) -> Result<HashSet<PathBuf>> {
+    if paths.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let mut child = Command::new("git")
+        .args(["check-ignore", "-z", "--stdin"])
+        .current_dir(directory)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    debug!("Checking {} paths against ignore rules in {}", paths.len(), directory);
+
+    // Feed stdin from another thread so a full stdout pipe can't block both sides
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input: Vec<u8> = paths
+        .iter()
+        .flat_map(|path| path.to_string_lossy().into_owned().into_bytes().into_iter().chain([0]))
+        .collect();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    let _ = writer.join();
+
+    // Exit status 1 means none of the paths are ignored
+    match output.status.code() {
+        Some(0) | Some(1) => Ok(output
+            .stdout
+            .split(|&byte| byte == 0)
+            .filter(|path| !path.is_empty())
+            .map(|path| PathBuf::from(String::from_utf8_lossy(path).into_owned()))
+            .collect()),
+        _ => Err(failure("Failed to check ignored paths", &String::from_utf8_lossy(&output.stderr))),
+    }
+}
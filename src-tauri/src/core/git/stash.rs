@@ -1,8 +1,11 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use std::process::Command;
 use tracing::{error, info};
 
+use super::command::{failure, GitCommand};
+
 pub fn stash(directory: &str, message: Option<String>) -> Result<()> {
+    let _span = crate::span!("git", op = "stash", directory = %directory).entered();
     info!("Stashing changes in {}", directory);
 
     let mut cmd = Command::new("git");
@@ -14,12 +17,12 @@ pub fn stash(directory: &str, message: Option<String>) -> Result<()> {
 
     cmd.current_dir(directory);
 
-    let output = cmd.output()?;
+    let output = cmd.run()?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         error!("Failed to stash changes: {}", stderr);
-        return Err(anyhow!("Failed to stash changes: {}", stderr));
+        return Err(failure("Failed to stash changes", &stderr));
     }
 
     info!("Successfully stashed changes");
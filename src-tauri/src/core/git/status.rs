@@ -3,9 +3,11 @@ use std::path::Path;
 use std::process::Command;
 use tracing::{debug, info};
 
+use super::command::GitCommand;
 use super::types::GitStatus;
 
 pub fn check_status(directory: &str) -> Result<GitStatus> {
+    let _span = crate::span!("git", op = "status", directory = %directory).entered();
     let path = Path::new(directory);
     
     // Check if directory exists
@@ -25,7 +27,7 @@ pub fn check_status(directory: &str) -> Result<GitStatus> {
         .arg("rev-parse")
         .arg("--is-inside-work-tree")
         .current_dir(directory)
-        .output()?;
+        .run()?;
 
     if !is_repo_output.status.success() {
         debug!("Directory {} is not a git repository", directory);
@@ -64,7 +66,7 @@ fn get_current_branch(directory: &str) -> Result<Option<String>> {
         .arg("--abbrev-ref")
         .arg("HEAD")
         .current_dir(directory)
-        .output()?;
+        .run()?;
 
     if output.status.success() {
         let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
@@ -82,7 +84,7 @@ fn check_for_changes(directory: &str) -> Result<(bool, bool)> {
         .arg("diff")
         .arg("--quiet")
         .current_dir(directory)
-        .output()?;
+        .run()?;
 
     let has_unstaged_changes = !unstaged_output.status.success();
 
@@ -92,7 +94,7 @@ fn check_for_changes(directory: &str) -> Result<(bool, bool)> {
         .arg("--cached")
         .arg("--quiet")
         .current_dir(directory)
-        .output()?;
+        .run()?;
 
     let has_staged_changes = !staged_output.status.success();
 
@@ -110,7 +112,7 @@ fn get_all_branches(directory: &str) -> Result<Vec<String>> {
         .arg("-a")
         .arg("--format=%(refname:short)")
         .current_dir(directory)
-        .output()?;
+        .run()?;
 
     if output.status.success() {
         let branches: Vec<String> = String::from_utf8_lossy(&output.stdout)
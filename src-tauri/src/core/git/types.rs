@@ -1,6 +1,7 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GitStatus {
     pub is_repo: bool,
@@ -30,4 +31,75 @@ impl Default for GitCheckoutOptions {
 #[serde(rename_all = "camelCase")]
 pub struct StashOptions {
     pub message: Option<String>,
-}
\ No newline at end of file
+}
+/// Snapshot of a worktree, including untracked files, taken before an agent turn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    pub id: String,
+    /// Commit holding the snapshot, under `refs/forge/checkpoints`
+    pub commit: String,
+    /// HEAD when the snapshot was taken, `None` before the first commit
+    pub head: Option<String>,
+    pub session_id: Option<String>,
+    /// Sequence number of the message the turn started with
+    pub message_seq: Option<u64>,
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// How `merge` brings a branch into its target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeMethod {
+    /// A merge commit, or a fast-forward when the target has not moved
+    #[default]
+    Merge,
+    /// Replay the branch's commits onto the target, then fast-forward the target
+    Rebase,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeOptions {
+    #[serde(default)]
+    pub method: MergeMethod,
+    /// Merge commit message, "Merge branch '<source>' into <target>" by default
+    pub message: Option<String>,
+    /// Create a merge commit even when the target could be fast-forwarded
+    #[serde(default)]
+    pub no_fast_forward: bool,
+}
+
+/// A file the trial merge could not combine, with each side's version. A version is `None`
+/// when the side deleted the file, or it is binary or too large to show.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictedFile {
+    pub path: String,
+    /// What git said about it, e.g. "CONFLICT (modify/delete): ..."
+    pub messages: Vec<String>,
+    /// Version in the common ancestor
+    pub base: Option<String>,
+    pub target: Option<String>,
+    pub source: Option<String>,
+}
+
+/// Result of merging `source` into `target`, or of trying to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeReport {
+    pub source: String,
+    pub target: String,
+    pub source_commit: String,
+    pub target_commit: String,
+    pub merge_base: Option<String>,
+    /// Commits on the source that the target doesn't have
+    pub commits_ahead: usize,
+    /// Commits on the target that the source doesn't have
+    pub commits_behind: usize,
+    /// Empty when the branches merge cleanly
+    pub conflicts: Vec<ConflictedFile>,
+    /// The target's new tip once merged; `None` for a trial merge or when there are conflicts
+    pub merged_commit: Option<String>,
+}
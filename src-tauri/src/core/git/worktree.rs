@@ -0,0 +1,87 @@
+use anyhow::Result;
+use std::process::Command;
+use tracing::{error, info};
+
+use super::command::{failure, GitCommand};
+
+/// Create a new worktree at `path` with a new branch, starting from `base` (HEAD by default)
+pub fn add(directory: &str, path: &str, branch: &str, base: Option<&str>) -> Result<()> {
+    let _span = crate::span!("git", op = "add_worktree", directory = %directory).entered();
+    info!("Creating worktree {} on branch {} in {}", path, branch, directory);
+
+    let mut cmd = Command::new("git");
+    cmd.arg("worktree")
+        .arg("add")
+        .arg("-b")
+        .arg(branch)
+        .arg(path);
+
+    if let Some(base) = base {
+        cmd.arg(base);
+    }
+
+    let output = cmd.current_dir(directory).run()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        error!("Failed to create worktree: {}", stderr);
+        return Err(failure("Failed to create worktree", &stderr));
+    }
+
+    info!("Successfully created worktree {}", path);
+    Ok(())
+}
+
+/// Worktree `branch` is checked out in, if any
+pub fn checked_out_at(directory: &str, branch: &str) -> Result<Option<String>> {
+    let output = Command::new("git")
+        .args(["worktree", "list", "--porcelain"])
+        .current_dir(directory)
+        .run()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(failure("Failed to list worktrees", &stderr));
+    }
+
+    let branch_ref = format!("branch refs/heads/{}", branch);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.split("\n\n").find_map(|entry| {
+        let mut lines = entry.lines();
+        let path = lines.next()?.strip_prefix("worktree ")?;
+        lines.any(|line| line == branch_ref).then(|| path.to_string())
+    }))
+}
+
+/// Create a worktree at `path` with the existing `branch` checked out
+pub fn checkout(directory: &str, path: &str, branch: &str) -> Result<()> {
+    let _span = crate::span!("git", op = "checkout_worktree", directory = %directory).entered();
+    info!("Creating worktree {} for branch {} in {}", path, branch, directory);
+
+    let output = Command::new("git")
+        .args(["worktree", "add", path, branch])
+        .current_dir(directory)
+        .run()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        error!("Failed to create worktree: {}", stderr);
+        return Err(failure("Failed to create worktree", &stderr));
+    }
+    Ok(())
+}
+
+/// Remove the worktree at `path`; git refuses while it has changes
+pub fn remove(directory: &str, path: &str) -> Result<()> {
+    let output = Command::new("git")
+        .args(["worktree", "remove", path])
+        .current_dir(directory)
+        .run()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(failure("Failed to remove worktree", &stderr));
+    }
+    info!("Removed worktree {}", path);
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde_json::json;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tracing::info;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use super::file::log_files;
+
+/// Only logs written in the last few days are useful for a bug report
+const BUNDLE_MAX_AGE: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+/// Stop adding files once the bundle holds this much uncompressed log data. The newest file is
+/// always included, cut to its most recent records if it alone is larger.
+const BUNDLE_MAX_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Zip recent logs from `log_dir` together with basic environment details
+pub fn create_bundle(log_dir: &Path, destination: &Path, log_level: &str) -> Result<usize> {
+    let now = SystemTime::now();
+    let mut total_bytes = 0;
+    let recent: Vec<_> = log_files(log_dir)
+        .into_iter()
+        .filter_map(|path| {
+            let metadata = path.metadata().ok()?;
+            let age = now.duration_since(metadata.modified().ok()?).unwrap_or_default();
+            (age <= BUNDLE_MAX_AGE).then_some((path, metadata.len()))
+        })
+        .enumerate()
+        .map_while(|(index, (path, size))| {
+            // How much of the end of the file to keep, if not all of it
+            let tail = (index == 0 && size > BUNDLE_MAX_BYTES).then_some(BUNDLE_MAX_BYTES);
+            total_bytes += tail.unwrap_or(size);
+            (total_bytes <= BUNDLE_MAX_BYTES).then_some((path, tail))
+        })
+        .collect();
+
+    if recent.is_empty() {
+        return Err(anyhow!("No recent logs in {}", log_dir.display()));
+    }
+
+    let mut zip = ZipWriter::new(File::create(destination)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let environment = json!({
+        "appVersion": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "logLevel": log_level,
+        "createdAt": Utc::now(),
+    });
+    zip.start_file("environment.json", options)?;
+    serde_json::to_writer_pretty(&mut zip, &environment)?;
+
+    for (path, tail) in &recent {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        zip.start_file(format!("logs/{}", name), options)?;
+        let mut file = File::open(path)?;
+        match *tail {
+            Some(tail) => {
+                // Start at the first whole record
+                let start = file.metadata()?.len().saturating_sub(tail);
+                let mut file = BufReader::new(file);
+                file.seek(SeekFrom::Start(start))?;
+                file.skip_until(b'\n')?;
+                io::copy(&mut file.take(tail), &mut zip)?;
+            }
+            None => {
+                io::copy(&mut file, &mut zip)?;
+            }
+        }
+    }
+    zip.finish()?;
+
+    info!("Bundled {} log files into {}", recent.len(), destination.display());
+    Ok(recent.len())
+}
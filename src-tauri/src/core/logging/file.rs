@@ -0,0 +1,265 @@
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const LOG_FILE_PREFIX: &str = "forge-app";
+const LOG_FILE_EXTENSION: &str = "log";
+
+/// When to start a new log file and which old ones to keep
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RetentionPolicy {
+    /// Start a new file once the current one reaches this size
+    pub max_file_size_bytes: u64,
+    /// Delete rotated files older than this
+    pub max_age_days: u64,
+    /// Keep at most this many rotated files
+    pub max_files: usize,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_file_size_bytes: 10 * 1024 * 1024,
+            max_age_days: 14,
+            max_files: 20,
+        }
+    }
+}
+
+/// Log file that rotates daily or when it grows too large, pruning old files as it goes
+pub struct RotatingFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+    opened_on: NaiveDate,
+    retention: RetentionPolicy,
+}
+
+impl RotatingFile {
+    pub fn open(dir: &Path, retention: RetentionPolicy) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = current_path(dir);
+
+        // Yesterday's log gets rotated before we append to it
+        let modified_on = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .map(|modified| DateTime::<Local>::from(modified).date_naive())
+            .ok();
+        let today = Local::now().date_naive();
+        if modified_on.is_some_and(|date| date != today) {
+            fs::rename(&path, rotated_path(dir))?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        let rotating = Self {
+            dir: dir.to_path_buf(),
+            file,
+            size,
+            opened_on: today,
+            retention,
+        };
+        rotating.prune();
+        Ok(rotating)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        fs::rename(current_path(&self.dir), rotated_path(&self.dir))?;
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(current_path(&self.dir))?;
+        self.size = 0;
+        self.opened_on = Local::now().date_naive();
+        self.prune();
+        Ok(())
+    }
+
+    /// Delete rotated files past the retention limits
+    fn prune(&self) {
+        let max_age = Duration::from_secs(self.retention.max_age_days * 24 * 60 * 60);
+        let now = SystemTime::now();
+
+        let rotated = log_files(&self.dir)
+            .into_iter()
+            .filter(|path| *path != current_path(&self.dir));
+        for (index, path) in rotated.enumerate() {
+            let expired = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age > max_age);
+            if expired || index >= self.retention.max_files {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let too_large = self.size > 0 && self.size + buf.len() as u64 > self.retention.max_file_size_bytes;
+        if too_large || Local::now().date_naive() != self.opened_on {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn current_path(dir: &Path) -> PathBuf {
+    dir.join(format!("{}.{}", LOG_FILE_PREFIX, LOG_FILE_EXTENSION))
+}
+
+fn rotated_path(dir: &Path) -> PathBuf {
+    let stamp = Local::now().format("%Y%m%d-%H%M%S%.3f");
+    dir.join(format!("{}.{}.{}", LOG_FILE_PREFIX, stamp, LOG_FILE_EXTENSION))
+}
+
+/// Log files in `dir`, newest first
+pub fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(LOG_FILE_PREFIX) && name.ends_with(LOG_FILE_EXTENSION)
+                })
+        })
+        .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty directory, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("forge-log-test-{}", uuid::Uuid::new_v4().simple()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn retention(max_file_size_bytes: u64, max_files: usize) -> RetentionPolicy {
+        RetentionPolicy {
+            max_file_size_bytes,
+            max_age_days: 14,
+            max_files,
+        }
+    }
+
+    /// Write a line, pausing so rotated file names, stamped to the millisecond, differ
+    fn write_line(file: &mut RotatingFile, line: &str) {
+        std::thread::sleep(Duration::from_millis(5));
+        file.write_all(line.as_bytes()).unwrap();
+    }
+
+    fn set_modified(path: &Path, age: Duration) {
+        let file = OpenOptions::new().append(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - age).unwrap();
+    }
+
+    #[test]
+    fn rotates_before_a_write_would_exceed_the_size_limit() {
+        let dir = TempDir::new();
+        let mut file = RotatingFile::open(&dir.0, retention(16, 10)).unwrap();
+        write_line(&mut file, "first record\n");
+        write_line(&mut file, "second record\n");
+        file.flush().unwrap();
+
+        let files = log_files(&dir.0);
+        assert_eq!(files.len(), 2);
+        assert_eq!(fs::read_to_string(current_path(&dir.0)).unwrap(), "second record\n");
+        let rotated = files.iter().find(|path| **path != current_path(&dir.0)).unwrap();
+        assert_eq!(fs::read_to_string(rotated).unwrap(), "first record\n");
+    }
+
+    #[test]
+    fn oversized_writes_to_an_empty_file_are_kept_whole() {
+        let dir = TempDir::new();
+        let mut file = RotatingFile::open(&dir.0, retention(4, 10)).unwrap();
+        write_line(&mut file, "déjà vu — 日本語\n");
+        file.flush().unwrap();
+
+        assert_eq!(log_files(&dir.0), [current_path(&dir.0)]);
+        assert_eq!(fs::read_to_string(current_path(&dir.0)).unwrap(), "déjà vu — 日本語\n");
+    }
+
+    #[test]
+    fn keeps_at_most_max_files_rotated_files() {
+        let dir = TempDir::new();
+        let mut file = RotatingFile::open(&dir.0, retention(1, 2)).unwrap();
+        for record in ["a\n", "b\n", "c\n", "d\n", "e\n"] {
+            write_line(&mut file, record);
+        }
+        file.flush().unwrap();
+
+        let contents: Vec<String> = log_files(&dir.0)
+            .iter()
+            .map(|path| fs::read_to_string(path).unwrap())
+            .collect();
+        assert_eq!(contents, ["e\n", "d\n", "c\n"]);
+    }
+
+    #[test]
+    fn open_rotates_an_old_file_and_prunes_expired_ones() {
+        let dir = TempDir::new();
+        let expired = dir.0.join(format!("{}.20200101-000000.000.{}", LOG_FILE_PREFIX, LOG_FILE_EXTENSION));
+        fs::write(&expired, "expired\n").unwrap();
+        set_modified(&expired, Duration::from_secs(30 * 24 * 60 * 60));
+        fs::write(current_path(&dir.0), "yesterday\n").unwrap();
+        set_modified(&current_path(&dir.0), Duration::from_secs(2 * 24 * 60 * 60));
+
+        let _file = RotatingFile::open(&dir.0, RetentionPolicy::default()).unwrap();
+
+        assert!(!expired.exists());
+        let files = log_files(&dir.0);
+        assert_eq!(files.len(), 2);
+        assert_eq!(fs::read_to_string(current_path(&dir.0)).unwrap(), "");
+        assert_eq!(fs::read_to_string(&files[1]).unwrap(), "yesterday\n");
+    }
+
+    #[test]
+    fn log_files_skip_other_files() {
+        let dir = TempDir::new();
+        assert!(log_files(&dir.0).is_empty());
+        assert!(log_files(&dir.0.join("missing")).is_empty());
+
+        fs::write(dir.0.join("notes.log"), "").unwrap();
+        fs::write(dir.0.join(format!("{}.txt", LOG_FILE_PREFIX)), "").unwrap();
+        fs::write(current_path(&dir.0), "").unwrap();
+        assert_eq!(log_files(&dir.0), [current_path(&dir.0)]);
+    }
+}
@@ -0,0 +1,277 @@
+mod bundle;
+mod file;
+mod stream;
+
+use anyhow::anyhow;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use tracing::{info, Level};
+use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
+use tracing_error::ErrorLayer;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::{fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry};
+
+pub use file::RetentionPolicy;
+pub use stream::{LogFilter, LogRecord};
+
+use file::RotatingFile;
+use stream::{LogBuffer, StreamLayer};
+
+use crate::core::error::{ErrorCode, ForgeError};
+
+/// Handles for changing the logging setup after startup
+struct LogControl {
+    filter: reload::Handle<EnvFilter, Registry>,
+    level: Mutex<String>,
+    file: FileOutput,
+    file_guard: Mutex<Option<WorkerGuard>>,
+    log_dir: Mutex<Option<PathBuf>>,
+    stream: Arc<LogBuffer>,
+}
+
+/// Records kept for the log viewer when it asks without a limit
+const DEFAULT_BACKFILL: usize = 500;
+
+static LOG_CONTROL: OnceLock<LogControl> = OnceLock::new();
+
+/// Writer for the JSON file layer; discards output until file logging is enabled
+#[derive(Clone, Default)]
+struct FileOutput(Arc<RwLock<Option<NonBlocking>>>);
+
+enum FileWriter {
+    File(NonBlocking),
+    Discard,
+}
+
+impl io::Write for FileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            FileWriter::File(writer) => writer.write(buf),
+            FileWriter::Discard => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            FileWriter::File(writer) => writer.flush(),
+            FileWriter::Discard => Ok(()),
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for FileOutput {
+    type Writer = FileWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        match self.0.read().unwrap().as_ref() {
+            Some(writer) => FileWriter::File(writer.clone()),
+            None => FileWriter::Discard,
+        }
+    }
+}
+
+fn default_level() -> Level {
+    if cfg!(debug_assertions) {
+        Level::DEBUG
+    } else {
+        Level::INFO
+    }
+}
+
+/// Build a filter from a level ("debug") applied to our crates, or from full
+/// `RUST_LOG`-style directives ("info,forge_app_lib::core::git=trace")
+fn build_filter(level: &str) -> anyhow::Result<EnvFilter> {
+    if level.contains('=') || level.contains(',') {
+        return Ok(EnvFilter::try_new(level)?);
+    }
+
+    let level: Level = level
+        .parse()
+        .map_err(|_| ForgeError::new(ErrorCode::InvalidInput, format!("Invalid log level: {}", level)))?;
+    Ok(EnvFilter::new(level.min(Level::INFO).to_string())
+        .add_directive(format!("forge_app={}", level).parse()?)
+        .add_directive(format!("forge_app_lib={}", level).parse()?)
+        .add_directive("tokio=warn".parse()?)
+        .add_directive("hyper=warn".parse()?))
+}
+
+/// Initialize the logging system with production-ready defaults.
+/// Logs go to the console; call [`enable_file_output`] once the log directory is known.
+pub fn init() -> anyhow::Result<()> {
+    // RUST_LOG takes precedence over the build's default level
+    let level = std::env::var("RUST_LOG").unwrap_or_else(|_| default_level().to_string());
+    let env_filter = build_filter(&level).or_else(|_| build_filter(&default_level().to_string()))?;
+    let (filter_layer, filter) = reload::Layer::new(env_filter);
+
+    // File layer with JSON formatting for structured logs
+    let file = FileOutput::default();
+    let file_layer = fmt::layer()
+        .with_writer(file.clone())
+        .with_ansi(false)
+        .with_target(true)
+        .with_thread_ids(true)
+        .with_file(true)
+        .with_line_number(true)
+        .json();
+
+    // Console logging layer: pretty in development, compact in release
+    let pretty_console = cfg!(debug_assertions).then(|| {
+        fmt::layer()
+            .with_ansi(true)
+            .with_target(true)
+            .with_thread_ids(true)
+            .with_file(true)
+            .with_line_number(true)
+            .with_level(true)
+            .pretty()
+    });
+    let compact_console = (!cfg!(debug_assertions)).then(|| {
+        fmt::layer()
+            .with_ansi(true)
+            .with_target(false)
+            .with_thread_ids(false)
+            .compact()
+    });
+
+    // Ring buffer feeding the in-app log viewer
+    let stream = Arc::new(LogBuffer::new());
+
+    // Create the subscriber
+    let subscriber = Registry::default()
+        .with(filter_layer)
+        .with(file_layer)
+        .with(StreamLayer::new(stream.clone()))
+        .with(ErrorLayer::default())
+        .with(pretty_console)
+        .with(compact_console);
+
+    // Initialize the global subscriber
+    subscriber.try_init()?;
+
+    let _ = LOG_CONTROL.set(LogControl {
+        filter,
+        level: Mutex::new(level),
+        file,
+        file_guard: Mutex::new(None),
+        log_dir: Mutex::new(None),
+        stream,
+    });
+
+    Ok(())
+}
+
+/// Console-only logging on stderr, leaving stdout to the headless CLI's events.
+/// Warnings and errors only, unless `RUST_LOG` asks for more.
+pub fn init_stderr() -> anyhow::Result<()> {
+    let level = std::env::var("RUST_LOG").unwrap_or_else(|_| Level::WARN.to_string());
+    let env_filter = build_filter(&level).or_else(|_| build_filter(&Level::WARN.to_string()))?;
+
+    fmt()
+        .with_env_filter(env_filter)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .with_target(false)
+        .compact()
+        .try_init()
+        .map_err(|e| anyhow!(e))
+}
+
+fn control() -> anyhow::Result<&'static LogControl> {
+    LOG_CONTROL
+        .get()
+        .ok_or_else(|| anyhow!("Logging is not initialized"))
+}
+
+/// Start writing JSON logs to rotating files in `log_dir`
+pub fn enable_file_output(log_dir: &Path, retention: RetentionPolicy) -> anyhow::Result<()> {
+    let control = control()?;
+    let file = RotatingFile::open(log_dir, retention)?;
+    let (writer, guard) = tracing_appender::non_blocking(file);
+
+    *control.file.0.write().unwrap() = Some(writer);
+    // Dropping a previous guard flushes whatever it still had buffered
+    *control.file_guard.lock().unwrap() = Some(guard);
+    *control.log_dir.lock().unwrap() = Some(log_dir.to_path_buf());
+
+    info!("Writing logs to {}", log_dir.display());
+    Ok(())
+}
+
+/// Directory log files are written to, if file logging is enabled
+pub fn log_dir() -> Option<PathBuf> {
+    LOG_CONTROL.get()?.log_dir.lock().unwrap().clone()
+}
+
+pub fn level() -> Option<String> {
+    LOG_CONTROL.get().map(|control| control.level.lock().unwrap().clone())
+}
+
+/// Change the log level without restarting, see [`build_filter`] for accepted values
+pub fn set_level(level: &str) -> anyhow::Result<()> {
+    let control = control()?;
+    let filter = build_filter(level)?;
+    control.filter.reload(filter)?;
+    *control.level.lock().unwrap() = level.to_string();
+
+    info!("Log level set to {}", level);
+    Ok(())
+}
+
+/// Buffered records matching `filter`, oldest first
+pub fn recent_records(filter: &LogFilter, limit: Option<usize>) -> anyhow::Result<Vec<LogRecord>> {
+    Ok(control()?.stream.recent(filter, limit.unwrap_or(DEFAULT_BACKFILL)))
+}
+
+/// Choose which new records are forwarded to `window`; `None` stops forwarding to it
+pub fn set_stream_filter(window: &str, filter: Option<LogFilter>) -> anyhow::Result<()> {
+    control()?.stream.set_filter(window, filter);
+    Ok(())
+}
+
+/// Hand every new record to `emit` once for each window whose stream filter it passes
+pub fn forward_records(emit: impl Fn(&str, &LogRecord) + Send + 'static) -> anyhow::Result<()> {
+    control()?.stream.forward(emit);
+    Ok(())
+}
+
+/// Zip recent log files to `destination` for attaching to a bug report.
+/// Returns the number of log files included.
+pub fn create_bundle(destination: &Path) -> anyhow::Result<usize> {
+    let log_dir = log_dir().ok_or_else(|| anyhow!("File logging is not enabled"))?;
+    bundle::create_bundle(&log_dir, destination, &level().unwrap_or_default())
+}
+
+/// Initialize logging for tests with minimal output
+#[cfg(test)]
+pub fn init_test() {
+    let _ = fmt()
+        .with_env_filter(EnvFilter::new("debug"))
+        .with_test_writer()
+        .try_init();
+}
+
+/// Helper to create a span for async operations
+#[macro_export]
+macro_rules! span {
+    ($name:expr) => {
+        tracing::info_span!($name)
+    };
+    ($name:expr, $($field:tt)*) => {
+        tracing::info_span!($name, $($field)*)
+    };
+}
+
+/// Helper for logging errors with context
+#[macro_export]
+macro_rules! log_error {
+    ($err:expr) => {
+        tracing::error!(error = ?$err, "Operation failed");
+    };
+    ($err:expr, $msg:expr) => {
+        tracing::error!(error = ?$err, $msg);
+    };
+    ($err:expr, $msg:expr, $($field:tt)*) => {
+        tracing::error!(error = ?$err, $($field)*, $msg);
+    };
+}
\ No newline at end of file
@@ -0,0 +1,271 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::broadcast;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Records kept for backfill when the log viewer opens
+const BUFFER_CAPACITY: usize = 5_000;
+/// Records the forwarder may fall behind by before it starts skipping
+const CHANNEL_CAPACITY: usize = 1_024;
+
+thread_local! {
+    /// Set on the forwarder thread so that logging done while emitting doesn't feed back into the stream
+    static FORWARDING: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogRecord {
+    /// Increases by one per record, so the viewer can tell if it missed any
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    pub fields: Map<String, Value>,
+    /// Fields of the enclosing spans, outermost first
+    pub context: Map<String, Value>,
+    pub spans: Vec<String>,
+    pub session_id: Option<String>,
+}
+
+/// Which records the log viewer wants to see
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFilter {
+    /// Most verbose level to include, e.g. "info" leaves out debug and trace
+    pub level: Option<String>,
+    /// Target prefix, e.g. "forge_app_lib::core::git"
+    pub target: Option<String>,
+    pub session_id: Option<String>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        if let Some(max) = self.level.as_deref().and_then(|level| level.parse::<Level>().ok()) {
+            if record.level.parse::<Level>().is_ok_and(|level| level > max) {
+                return false;
+            }
+        }
+        if let Some(target) = &self.target {
+            if !record.target.starts_with(target.as_str()) {
+                return false;
+            }
+        }
+        if let Some(session_id) = &self.session_id {
+            if record.session_id.as_ref() != Some(session_id) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Recent records plus a channel announcing new ones
+pub struct LogBuffer {
+    records: Mutex<VecDeque<LogRecord>>,
+    next_seq: Mutex<u64>,
+    sender: broadcast::Sender<LogRecord>,
+    /// What to forward to each watching window, by window label
+    filters: RwLock<HashMap<String, LogFilter>>,
+}
+
+impl LogBuffer {
+    pub fn new() -> Self {
+        Self {
+            records: Mutex::new(VecDeque::with_capacity(BUFFER_CAPACITY)),
+            next_seq: Mutex::new(0),
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+            filters: RwLock::default(),
+        }
+    }
+
+    fn push(&self, mut record: LogRecord) {
+        let mut records = self.records.lock().unwrap();
+        let mut next_seq = self.next_seq.lock().unwrap();
+        record.seq = *next_seq;
+        *next_seq += 1;
+
+        if records.len() == BUFFER_CAPACITY {
+            records.pop_front();
+        }
+        records.push_back(record.clone());
+
+        // Only fails when nobody is listening
+        let _ = self.sender.send(record);
+    }
+
+    /// The most recent buffered records matching `filter`, oldest first
+    pub fn recent(&self, filter: &LogFilter, limit: usize) -> Vec<LogRecord> {
+        let records = self.records.lock().unwrap();
+        let mut recent: Vec<LogRecord> = records
+            .iter()
+            .rev()
+            .filter(|record| filter.matches(record))
+            .take(limit)
+            .cloned()
+            .collect();
+        recent.reverse();
+        recent
+    }
+
+    /// Choose what `window` is sent; `None` stops sending it anything
+    pub fn set_filter(&self, window: &str, filter: Option<LogFilter>) {
+        let mut filters = self.filters.write().unwrap();
+        match filter {
+            Some(filter) => filters.insert(window.to_string(), filter),
+            None => filters.remove(window),
+        };
+    }
+
+    /// Call `emit` with every new record and each window whose filter it matches, on a dedicated thread
+    pub fn forward(self: &Arc<Self>, emit: impl Fn(&str, &LogRecord) + Send + 'static) {
+        let buffer = self.clone();
+        let mut receiver = self.sender.subscribe();
+        std::thread::Builder::new()
+            .name("log-forwarder".to_string())
+            .spawn(move || {
+                FORWARDING.with(|forwarding| forwarding.set(true));
+                loop {
+                    let record = match receiver.blocking_recv() {
+                        Ok(record) => record,
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                    let windows: Vec<String> = buffer
+                        .filters
+                        .read()
+                        .unwrap()
+                        .iter()
+                        .filter(|(_, filter)| filter.matches(&record))
+                        .map(|(window, _)| window.clone())
+                        .collect();
+                    for window in windows {
+                        emit(&window, &record);
+                    }
+                }
+            })
+            .expect("failed to spawn log forwarder thread");
+    }
+}
+
+/// Collects field values as JSON
+#[derive(Default)]
+struct JsonVisitor(Map<String, Value>);
+
+impl Visit for JsonVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), Value::from(format!("{:?}", value)));
+    }
+}
+
+/// Span fields, kept in the span's extensions
+struct SpanFields(Map<String, Value>);
+
+/// Tracing layer feeding a [`LogBuffer`]
+pub struct StreamLayer {
+    buffer: Arc<LogBuffer>,
+}
+
+impl StreamLayer {
+    pub fn new(buffer: Arc<LogBuffer>) -> Self {
+        Self { buffer }
+    }
+}
+
+impl<S> Layer<S> for StreamLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = JsonVisitor::default();
+        attrs.record(&mut visitor);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(visitor.0));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+            let mut visitor = JsonVisitor::default();
+            values.record(&mut visitor);
+            fields.extend(visitor.0);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if FORWARDING.with(|forwarding| forwarding.get()) {
+            return;
+        }
+
+        let mut visitor = JsonVisitor::default();
+        event.record(&mut visitor);
+        let mut fields = visitor.0;
+        let message = match fields.remove("message") {
+            Some(Value::String(message)) => message,
+            Some(other) => other.to_string(),
+            None => String::new(),
+        };
+
+        let mut context = Map::new();
+        let mut spans = Vec::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                spans.push(span.name().to_string());
+                if let Some(SpanFields(span_fields)) = span.extensions().get::<SpanFields>() {
+                    context.extend(span_fields.clone());
+                }
+            }
+        }
+        let session_id = context
+            .get("session_id")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        let metadata = event.metadata();
+        self.buffer.push(LogRecord {
+            seq: 0,
+            timestamp: Utc::now(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message,
+            fields,
+            context,
+            spans,
+            session_id,
+        });
+    }
+}
@@ -0,0 +1,371 @@
+use anyhow::{anyhow, Result};
+use futures::{Stream, StreamExt};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::process::Stdio;
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{ChildStdin, ChildStdout, Command};
+use tokio::time::{timeout, Duration};
+use tracing::{debug, info, warn};
+
+use super::types::{McpHealth, McpServer, McpTool, McpTransport};
+
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(20);
+const PROTOCOL_VERSION: &str = "2025-03-26";
+const INITIALIZE_ID: u64 = 1;
+const LIST_TOOLS_ID: u64 = 2;
+
+/// Launch (or connect to) a server, run the MCP handshake and list its tools
+pub async fn check(server: &McpServer) -> McpHealth {
+    info!("Checking MCP server {}", server.name);
+    let started = Instant::now();
+
+    let handshake = async {
+        match &server.transport {
+            McpTransport::Stdio { command, args, env } => check_stdio(command, args, env).await,
+            McpTransport::Http { url, headers } => check_http(url, headers).await,
+            McpTransport::Sse { url, headers } => check_sse(url, headers).await,
+        }
+    };
+    let result = timeout(HEALTH_CHECK_TIMEOUT, handshake)
+        .await
+        .unwrap_or_else(|_| {
+            Err(anyhow!("Timed out after {}s", HEALTH_CHECK_TIMEOUT.as_secs()))
+        });
+
+    let duration_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok((server_info, tools)) => {
+            info!("MCP server {} is healthy with {} tools", server.name, tools.len());
+            McpHealth {
+                name: server.name.clone(),
+                healthy: true,
+                server_info,
+                tools,
+                error: None,
+                duration_ms,
+            }
+        }
+        Err(e) => {
+            warn!("MCP server {} failed health check: {}", server.name, e);
+            McpHealth {
+                name: server.name.clone(),
+                healthy: false,
+                server_info: None,
+                tools: Vec::new(),
+                error: Some(e.to_string()),
+                duration_ms,
+            }
+        }
+    }
+}
+
+type Handshake = (Option<Value>, Vec<McpTool>);
+
+fn initialize_request() -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": INITIALIZE_ID,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "forge", "version": env!("CARGO_PKG_VERSION") }
+        }
+    })
+}
+
+fn initialized_notification() -> Value {
+    json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })
+}
+
+fn list_tools_request() -> Value {
+    json!({ "jsonrpc": "2.0", "id": LIST_TOOLS_ID, "method": "tools/list", "params": {} })
+}
+
+/// Unwrap a JSON-RPC response into its result
+fn into_result(response: Value) -> Result<Value> {
+    if let Some(error) = response.get("error") {
+        return Err(anyhow!("Server returned an error: {}", error));
+    }
+    response
+        .get("result")
+        .cloned()
+        .ok_or_else(|| anyhow!("Response has no result: {}", response))
+}
+
+fn handshake_from(initialize: Value, tools: Value) -> Result<Handshake> {
+    let server_info = initialize.get("serverInfo").cloned();
+    let tools = serde_json::from_value(tools.get("tools").cloned().unwrap_or_else(|| json!([])))?;
+    Ok((server_info, tools))
+}
+
+async fn check_stdio(
+    command: &str,
+    args: &[String],
+    env: &HashMap<String, String>,
+) -> Result<Handshake> {
+    let mut child = Command::new(command)
+        .args(args)
+        .envs(env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| anyhow!("Failed to launch {}: {}", command, e))?;
+
+    let mut stdin = child.stdin.take().ok_or_else(|| anyhow!("Failed to capture stdin"))?;
+    let stdout = child.stdout.take().ok_or_else(|| anyhow!("Failed to capture stdout"))?;
+    let mut lines = BufReader::new(stdout).lines();
+
+    let handshake = async {
+        write_stdio(&mut stdin, &initialize_request()).await?;
+        let initialize = read_stdio_response(&mut lines, INITIALIZE_ID).await?;
+        write_stdio(&mut stdin, &initialized_notification()).await?;
+        write_stdio(&mut stdin, &list_tools_request()).await?;
+        let tools = read_stdio_response(&mut lines, LIST_TOOLS_ID).await?;
+        handshake_from(initialize, tools)
+    }
+    .await;
+
+    let _ = child.start_kill();
+
+    let error = match handshake {
+        Ok(handshake) => return Ok(handshake),
+        Err(e) => e,
+    };
+
+    // The server's stderr usually explains why it failed
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let _ = timeout(Duration::from_secs(1), pipe.read_to_string(&mut stderr)).await;
+    }
+    match stderr.trim() {
+        "" => Err(error),
+        stderr => Err(anyhow!("{}\n{}", error, stderr)),
+    }
+}
+
+async fn write_stdio(stdin: &mut ChildStdin, message: &Value) -> Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stdin.write_all(line.as_bytes()).await?;
+    stdin.flush().await?;
+    Ok(())
+}
+
+async fn read_stdio_response(lines: &mut Lines<BufReader<ChildStdout>>, id: u64) -> Result<Value> {
+    while let Some(line) = lines.next_line().await? {
+        match serde_json::from_str::<Value>(&line) {
+            Ok(message) if message.get("id").and_then(Value::as_u64) == Some(id) => {
+                return into_result(message);
+            }
+            Ok(_) => debug!("Skipping MCP message: {}", line),
+            Err(_) => debug!("Skipping non-JSON output: {}", line),
+        }
+    }
+    Err(anyhow!("Server exited before responding"))
+}
+
+/// Streamable HTTP transport: every request is a POST, answered with JSON or an SSE stream
+async fn check_http(url: &str, headers: &HashMap<String, String>) -> Result<Handshake> {
+    let client = reqwest::Client::new();
+
+    let (initialize, session_id) =
+        post_http(&client, url, headers, None, &initialize_request(), Some(INITIALIZE_ID)).await?;
+    post_http(&client, url, headers, session_id.as_deref(), &initialized_notification(), None)
+        .await?;
+    let (tools, _) = post_http(
+        &client,
+        url,
+        headers,
+        session_id.as_deref(),
+        &list_tools_request(),
+        Some(LIST_TOOLS_ID),
+    )
+    .await?;
+
+    handshake_from(initialize.unwrap_or_default(), tools.unwrap_or_default())
+}
+
+async fn post_http(
+    client: &reqwest::Client,
+    url: &str,
+    headers: &HashMap<String, String>,
+    session_id: Option<&str>,
+    message: &Value,
+    id: Option<u64>,
+) -> Result<(Option<Value>, Option<String>)> {
+    let mut request = client
+        .post(url)
+        .header(ACCEPT, "application/json, text/event-stream")
+        .json(message);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    if let Some(session_id) = session_id {
+        request = request.header("Mcp-Session-Id", session_id);
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow!("Server responded with {}: {}", status, body));
+    }
+
+    let session_id = response
+        .headers()
+        .get("mcp-session-id")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let Some(id) = id else {
+        return Ok((None, session_id));
+    };
+
+    let is_stream = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+
+    let body = if is_stream {
+        let mut events = SseStream::new(Box::pin(response.bytes_stream()));
+        read_sse_response(&mut events, id).await?
+    } else {
+        response.json::<Value>().await?
+    };
+
+    Ok((Some(into_result(body)?), session_id))
+}
+
+/// Legacy HTTP+SSE transport: responses arrive on a long-lived event stream,
+/// requests are POSTed to the endpoint announced on that stream
+async fn check_sse(url: &str, headers: &HashMap<String, String>) -> Result<Handshake> {
+    let client = reqwest::Client::new();
+
+    let mut request = client.get(url).header(ACCEPT, "text/event-stream");
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Server responded with {}", response.status()));
+    }
+
+    let mut events = SseStream::new(Box::pin(response.bytes_stream()));
+    let endpoint = loop {
+        let event = events
+            .next()
+            .await?
+            .ok_or_else(|| anyhow!("Stream closed before the server announced its endpoint"))?;
+        if event.event == "endpoint" {
+            break reqwest::Url::parse(url)?.join(event.data.trim())?;
+        }
+    };
+    debug!("MCP SSE endpoint: {}", endpoint);
+
+    let post = |message: Value| {
+        let mut request = client.post(endpoint.clone()).json(&message);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        async move {
+            let response = request.send().await?;
+            if !response.status().is_success() {
+                return Err(anyhow!("Server responded with {}", response.status()));
+            }
+            Ok::<_, anyhow::Error>(())
+        }
+    };
+
+    post(initialize_request()).await?;
+    let initialize = into_result(read_sse_response(&mut events, INITIALIZE_ID).await?)?;
+    post(initialized_notification()).await?;
+    post(list_tools_request()).await?;
+    let tools = into_result(read_sse_response(&mut events, LIST_TOOLS_ID).await?)?;
+
+    handshake_from(initialize, tools)
+}
+
+async fn read_sse_response<S, B>(events: &mut SseStream<S>, id: u64) -> Result<Value>
+where
+    S: Stream<Item = reqwest::Result<B>> + Unpin,
+    B: AsRef<[u8]>,
+{
+    while let Some(event) = events.next().await? {
+        if event.event != "message" {
+            continue;
+        }
+        match serde_json::from_str::<Value>(&event.data) {
+            Ok(message) if message.get("id").and_then(Value::as_u64) == Some(id) => {
+                return Ok(message);
+            }
+            _ => debug!("Skipping MCP event: {}", event.data),
+        }
+    }
+    Err(anyhow!("Stream closed before the server responded"))
+}
+
+struct SseEvent {
+    event: String,
+    data: String,
+}
+
+/// Minimal server-sent events reader over a byte stream
+struct SseStream<S> {
+    inner: S,
+    buffer: String,
+    pending: VecDeque<SseEvent>,
+}
+
+impl<S, B> SseStream<S>
+where
+    S: Stream<Item = reqwest::Result<B>> + Unpin,
+    B: AsRef<[u8]>,
+{
+    fn new(inner: S) -> Self {
+        Self {
+            inner,
+            buffer: String::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    async fn next(&mut self) -> Result<Option<SseEvent>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+
+            let Some(chunk) = self.inner.next().await else {
+                return Ok(None);
+            };
+            self.buffer
+                .push_str(&String::from_utf8_lossy(chunk?.as_ref()).replace("\r\n", "\n"));
+
+            while let Some(end) = self.buffer.find("\n\n") {
+                let raw: String = self.buffer.drain(..end + 2).collect();
+                let mut event = SseEvent {
+                    event: "message".to_string(),
+                    data: String::new(),
+                };
+                for line in raw.lines() {
+                    if let Some(value) = line.strip_prefix("event:") {
+                        event.event = value.trim().to_string();
+                    } else if let Some(value) = line.strip_prefix("data:") {
+                        if !event.data.is_empty() {
+                            event.data.push('\n');
+                        }
+                        event.data.push_str(value.strip_prefix(' ').unwrap_or(value));
+                    }
+                }
+                self.pending.push_back(event);
+            }
+        }
+    }
+}
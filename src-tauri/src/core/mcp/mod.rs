@@ -0,0 +1,7 @@
+pub mod health;
+pub mod registry;
+pub mod types;
+
+pub use health::check as check_server;
+pub use registry::{McpConfigFile, McpRegistry};
+pub use types::{McpHealth, McpServer};
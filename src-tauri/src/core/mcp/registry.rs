@@ -0,0 +1,245 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::types::{McpServer, McpTransport};
+use crate::core::error::{ErrorCode, ForgeError};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RegistryData {
+    servers: BTreeMap<String, McpTransport>,
+    /// Enabled server names per agent id
+    agents: HashMap<String, BTreeSet<String>>,
+}
+
+/// Known MCP servers and which agents use them
+#[derive(Default)]
+pub struct McpRegistry {
+    data: RwLock<RegistryData>,
+    storage_path: RwLock<Option<PathBuf>>,
+    /// Where `--mcp-config` files are written while queries run
+    runtime_dir: RwLock<Option<PathBuf>>,
+}
+
+/// A `--mcp-config` file for one query, deleted when dropped. It holds the servers' `env` and
+/// `headers`, often API tokens, so only the current user can read it.
+pub struct McpConfigFile {
+    path: PathBuf,
+}
+
+impl McpConfigFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for McpConfigFile {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            warn!("Failed to remove MCP config {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+impl McpRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the registry from `path` and keep saving changes there
+    pub fn load(&self, path: PathBuf) -> Result<()> {
+        if path.exists() {
+            let contents = fs::read_to_string(&path)?;
+            let data: RegistryData = serde_json::from_str(&contents)
+                .map_err(|e| anyhow!("Invalid MCP registry {}: {}", path.display(), e))?;
+            info!("Loaded {} MCP servers from {}", data.servers.len(), path.display());
+            *self.data.write().unwrap() = data;
+        }
+
+        *self.storage_path.write().unwrap() = Some(path);
+        Ok(())
+    }
+
+    /// Write `--mcp-config` files under `dir` rather than next to the registry
+    pub fn set_runtime_dir(&self, dir: PathBuf) {
+        *self.runtime_dir.write().unwrap() = Some(dir);
+    }
+
+    pub fn list_servers(&self) -> Vec<McpServer> {
+        let data = self.data.read().unwrap();
+        data.servers
+            .iter()
+            .map(|(name, transport)| McpServer {
+                name: name.clone(),
+                transport: transport.clone(),
+            })
+            .collect()
+    }
+
+    pub fn get_server(&self, name: &str) -> Option<McpServer> {
+        let data = self.data.read().unwrap();
+        data.servers.get(name).map(|transport| McpServer {
+            name: name.to_string(),
+            transport: transport.clone(),
+        })
+    }
+
+    /// Add a server or replace the one with the same name
+    pub fn save_server(&self, server: McpServer) -> Result<()> {
+        if server.name.trim().is_empty() {
+            return Err(ForgeError::new(ErrorCode::InvalidInput, "MCP server name cannot be empty").into());
+        }
+        match &server.transport {
+            McpTransport::Stdio { command, .. } if command.trim().is_empty() => {
+                return Err(ForgeError::new(ErrorCode::InvalidInput, format!("MCP server {} has no command", server.name)).into());
+            }
+            McpTransport::Http { url, .. } | McpTransport::Sse { url, .. }
+                if !url.starts_with("http://") && !url.starts_with("https://") =>
+            {
+                return Err(ForgeError::new(
+                    ErrorCode::InvalidInput,
+                    format!("MCP server {} has an invalid URL: {}", server.name, url),
+                )
+                .into());
+            }
+            _ => {}
+        }
+
+        info!("Saving MCP server {}", server.name);
+        self.data.write().unwrap().servers.insert(server.name, server.transport);
+        self.persist()
+    }
+
+    pub fn remove_server(&self, name: &str) -> Result<()> {
+        {
+            let mut data = self.data.write().unwrap();
+            if data.servers.remove(name).is_none() {
+                return Err(ForgeError::new(ErrorCode::NotFound, format!("MCP server not found: {}", name)).into());
+            }
+            for enabled in data.agents.values_mut() {
+                enabled.remove(name);
+            }
+        }
+
+        info!("Removed MCP server {}", name);
+        self.persist()
+    }
+
+    /// Replace the set of servers enabled for an agent
+    pub fn set_agent_servers(&self, agent_id: &str, names: Vec<String>) -> Result<()> {
+        {
+            let mut data = self.data.write().unwrap();
+            if let Some(unknown) = names.iter().find(|name| !data.servers.contains_key(*name)) {
+                return Err(ForgeError::new(ErrorCode::NotFound, format!("MCP server not found: {}", unknown)).into());
+            }
+
+            if names.is_empty() {
+                data.agents.remove(agent_id);
+            } else {
+                data.agents.insert(agent_id.to_string(), names.into_iter().collect());
+            }
+        }
+
+        self.persist()
+    }
+
+    /// Enable servers for an agent for as long as this registry lives, without saving it
+    pub fn enable_servers(&self, agent_id: &str, names: &[String]) -> Result<()> {
+        let mut data = self.data.write().unwrap();
+        if let Some(unknown) = names.iter().find(|name| !data.servers.contains_key(*name)) {
+            return Err(ForgeError::new(ErrorCode::NotFound, format!("MCP server not found: {}", unknown)).into());
+        }
+        data.agents.entry(agent_id.to_string()).or_default().extend(names.iter().cloned());
+        Ok(())
+    }
+
+    pub fn agent_servers(&self, agent_id: &str) -> Vec<McpServer> {
+        let data = self.data.read().unwrap();
+        data.agents
+            .get(agent_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|name| {
+                data.servers.get(name).map(|transport| McpServer {
+                    name: name.clone(),
+                    transport: transport.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Write the `--mcp-config` file for an agent's servers for a query in `session_id`.
+    /// Returns `None` if the agent has no servers enabled.
+    pub fn write_cli_config(&self, agent_id: &str, session_id: &str) -> Result<Option<McpConfigFile>> {
+        let servers = self.agent_servers(agent_id);
+        if servers.is_empty() {
+            return Ok(None);
+        }
+
+        let mcp_servers: serde_json::Map<String, serde_json::Value> = servers
+            .into_iter()
+            .map(|server| Ok((server.name, serde_json::to_value(server.transport)?)))
+            .collect::<Result<_>>()?;
+
+        let dir = self.runtime_dir();
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(&dir)?;
+
+        // A fresh name each time, so nothing already there is written through
+        let path = dir.join(format!("{}-{}.json", session_id, Uuid::new_v4().simple()));
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = McpConfigFile { path };
+        options
+            .open(file.path())?
+            .write_all(serde_json::to_string_pretty(&json!({ "mcpServers": mcp_servers }))?.as_bytes())?;
+
+        debug!("Wrote MCP config for agent {} to {}", agent_id, file.path().display());
+        Ok(Some(file))
+    }
+
+    /// The runtime directory if set, else a folder next to the registry file, else a private
+    /// folder in the temp directory
+    fn runtime_dir(&self) -> PathBuf {
+        if let Some(dir) = self.runtime_dir.read().unwrap().clone() {
+            return dir;
+        }
+        match self.storage_path.read().unwrap().as_deref().and_then(Path::parent) {
+            Some(parent) => parent.join("mcp-runtime"),
+            None => std::env::temp_dir().join(format!("forge-mcp-{}", std::process::id())),
+        }
+    }
+
+    fn persist(&self) -> Result<()> {
+        let Some(path) = self.storage_path.read().unwrap().clone() else {
+            warn!("MCP registry has no storage path, changes will not be saved");
+            return Ok(());
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let contents = serde_json::to_string_pretty(&*self.data.read().unwrap())?;
+        fs::write(&path, contents)?;
+        Ok(())
+    }
+}
@@ -1,4 +1,9 @@
+pub mod agents;
 pub mod claude;
 pub mod error;
 pub mod git;
-pub mod logging;
\ No newline at end of file
+pub mod logging;
+pub mod mcp;
+pub mod projects;
+pub mod search;
+pub mod watch;
\ No newline at end of file
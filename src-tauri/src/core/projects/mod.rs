@@ -0,0 +1,5 @@
+pub mod registry;
+pub mod types;
+
+pub use registry::ProjectRegistry;
+pub use types::{Project, ProjectSettings, ProjectUpdate, ProjectWorktree};
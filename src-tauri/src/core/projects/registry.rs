@@ -0,0 +1,233 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::types::{Project, ProjectUpdate, ProjectWorktree};
+use crate::core::claude::AgentConfig;
+use crate::core::error::{ErrorCode, ForgeError};
+use crate::core::git;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RegistryData {
+    projects: BTreeMap<String, Project>,
+}
+
+/// Projects the user has opened, saved across restarts
+#[derive(Default)]
+pub struct ProjectRegistry {
+    data: RwLock<RegistryData>,
+    storage_path: RwLock<Option<PathBuf>>,
+}
+
+impl ProjectRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the registry from `path` and keep saving changes there
+    pub fn load(&self, path: PathBuf) -> Result<()> {
+        if path.exists() {
+            let contents = fs::read_to_string(&path)?;
+            let data: RegistryData = serde_json::from_str(&contents)
+                .map_err(|e| anyhow!("Invalid project registry {}: {}", path.display(), e))?;
+            info!("Loaded {} projects from {}", data.projects.len(), path.display());
+            *self.data.write().unwrap() = data;
+        }
+
+        *self.storage_path.write().unwrap() = Some(path);
+        Ok(())
+    }
+
+    /// Every project, by name
+    pub fn list(&self) -> Vec<Project> {
+        let mut projects: Vec<_> = self.data.read().unwrap().projects.values().cloned().collect();
+        projects.sort_by_key(|project| project.name.to_lowercase());
+        projects
+    }
+
+    /// The `limit` most recently opened projects, newest first
+    pub fn recent(&self, limit: usize) -> Vec<Project> {
+        let mut projects: Vec<_> = self.data.read().unwrap().projects.values().cloned().collect();
+        projects.sort_by_key(|project| Reverse(project.last_opened_at));
+        projects.truncate(limit);
+        projects
+    }
+
+    pub fn get(&self, id: &str) -> Result<Project> {
+        self.data
+            .read()
+            .unwrap()
+            .projects
+            .get(id)
+            .cloned()
+            .ok_or_else(|| project_not_found(id))
+    }
+
+    /// The project rooted at `path`, created on first open. Marks it as the most recently opened.
+    pub fn open(&self, path: &str) -> Result<Project> {
+        let root = Path::new(path).canonicalize().map_err(|e| {
+            ForgeError::new(ErrorCode::NotFound, format!("Cannot open project {}: {}", path, e))
+                .with_details(serde_json::json!({ "path": path }))
+        })?;
+        if !root.is_dir() {
+            return Err(ForgeError::new(ErrorCode::InvalidInput, format!("{} is not a directory", path))
+                .with_details(serde_json::json!({ "path": path }))
+                .into());
+        }
+        let root_path = root.to_string_lossy().into_owned();
+
+        let project = {
+            let mut data = self.data.write().unwrap();
+            let now = Utc::now();
+            match data.projects.values_mut().find(|project| project.root_path == root_path) {
+                Some(project) => {
+                    project.last_opened_at = now;
+                    project.clone()
+                }
+                None => {
+                    let project = Project {
+                        id: Uuid::new_v4().to_string(),
+                        name: root
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_else(|| root_path.clone()),
+                        default_branch: git::default_branch(&root_path),
+                        root_path,
+                        agents: Vec::new(),
+                        worktrees: Vec::new(),
+                        settings: Default::default(),
+                        created_at: now,
+                        last_opened_at: now,
+                    };
+                    info!("Created project {} at {}", project.id, project.root_path);
+                    data.projects.insert(project.id.clone(), project.clone());
+                    project
+                }
+            }
+        };
+
+        self.persist()?;
+        Ok(project)
+    }
+
+    pub fn update(&self, id: &str, update: ProjectUpdate) -> Result<Project> {
+        if update.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+            return Err(ForgeError::new(ErrorCode::InvalidInput, "Project name cannot be empty").into());
+        }
+
+        let project = self.modify(id, |project| {
+            if let Some(name) = update.name {
+                project.name = name;
+            }
+            if let Some(branch) = update.default_branch {
+                project.default_branch = Some(branch);
+            }
+            if let Some(settings) = update.settings {
+                project.settings = settings;
+            }
+            Ok(())
+        })?;
+        info!("Updated project {}", id);
+        Ok(project)
+    }
+
+    /// Forget a project; its files and worktrees stay on disk
+    pub fn remove(&self, id: &str) -> Result<()> {
+        if self.data.write().unwrap().projects.remove(id).is_none() {
+            return Err(project_not_found(id));
+        }
+        info!("Removed project {}", id);
+        self.persist()
+    }
+
+    /// Add an agent to the project or replace the one with the same id
+    pub fn save_agent(&self, id: &str, agent: AgentConfig) -> Result<Project> {
+        self.modify(id, |project| {
+            if let Some(directory) = &agent.working_directory {
+                project.check_directory(directory)?;
+            }
+            match project.agents.iter_mut().find(|existing| existing.id == agent.id) {
+                Some(existing) => *existing = agent,
+                None => project.agents.push(agent),
+            }
+            Ok(())
+        })
+    }
+
+    pub fn remove_agent(&self, id: &str, agent_id: &str) -> Result<Project> {
+        self.modify(id, |project| {
+            let count = project.agents.len();
+            project.agents.retain(|agent| agent.id != agent_id);
+            if project.agents.len() == count {
+                return Err(ForgeError::new(ErrorCode::NotFound, format!("Agent {} is not in project {}", agent_id, project.name))
+                    .with_details(serde_json::json!({ "projectId": project.id, "agentId": agent_id }))
+                    .into());
+            }
+            Ok(())
+        })
+    }
+
+    /// Record a worktree created for the project
+    pub fn add_worktree(&self, id: &str, worktree: ProjectWorktree) -> Result<Project> {
+        self.modify(id, |project| {
+            project.worktrees.retain(|existing| existing.path != worktree.path);
+            project.worktrees.push(worktree);
+            Ok(())
+        })
+    }
+
+    /// `directory` checked against the project, or the project's root when it is not given
+    pub fn resolve_directory(&self, id: &str, directory: Option<&str>) -> Result<String> {
+        let project = self.get(id)?;
+        match directory {
+            Some(directory) => {
+                project.check_directory(directory)?;
+                Ok(directory.to_string())
+            }
+            None => Ok(project.root_path),
+        }
+    }
+
+    /// Apply `change` to a project and save, leaving it untouched if `change` fails
+    fn modify(&self, id: &str, change: impl FnOnce(&mut Project) -> Result<()>) -> Result<Project> {
+        let project = {
+            let mut data = self.data.write().unwrap();
+            let project = data.projects.get_mut(id).ok_or_else(|| project_not_found(id))?;
+            let mut changed = project.clone();
+            change(&mut changed)?;
+            *project = changed.clone();
+            changed
+        };
+
+        self.persist()?;
+        Ok(project)
+    }
+
+    fn persist(&self) -> Result<()> {
+        let Some(path) = self.storage_path.read().unwrap().clone() else {
+            warn!("Project registry has no storage path, changes will not be saved");
+            return Ok(());
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let contents = serde_json::to_string_pretty(&*self.data.read().unwrap())?;
+        fs::write(&path, contents)?;
+        Ok(())
+    }
+}
+
+fn project_not_found(id: &str) -> anyhow::Error {
+    ForgeError::new(ErrorCode::NotFound, format!("Project not found: {}", id))
+        .with_details(serde_json::json!({ "projectId": id }))
+        .into()
+}
@@ -0,0 +1,105 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::core::agents::PermissionMode;
+use crate::core::claude::AgentConfig;
+use crate::core::error::{ErrorCode, ForgeError};
+
+/// A repository the user works in, with the agents and worktrees that belong to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    /// Canonical path of the project's main directory
+    pub root_path: String,
+    /// Branch agent worktrees start from
+    pub default_branch: Option<String>,
+    #[serde(default)]
+    pub agents: Vec<AgentConfig>,
+    #[serde(default)]
+    pub worktrees: Vec<ProjectWorktree>,
+    #[serde(default)]
+    pub settings: ProjectSettings,
+    pub created_at: DateTime<Utc>,
+    pub last_opened_at: DateTime<Utc>,
+}
+
+/// A git worktree created for the project, usually outside its root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectWorktree {
+    pub path: String,
+    pub branch: String,
+    /// Agent the worktree was made for
+    pub agent_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Defaults for queries run in the project when the agent and prompt leave them unset
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSettings {
+    pub model: Option<String>,
+    pub permission_mode: Option<PermissionMode>,
+    /// Where new worktrees go; defaults to `<root>-worktrees` next to the root
+    pub worktree_dir: Option<String>,
+}
+
+/// Fields of a project the user can change; unset fields are left as they are
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectUpdate {
+    pub name: Option<String>,
+    pub default_branch: Option<String>,
+    pub settings: Option<ProjectSettings>,
+}
+
+impl Project {
+    /// The root and every worktree of the project
+    pub fn directories(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.root_path.as_str()).chain(self.worktrees.iter().map(|worktree| worktree.path.as_str()))
+    }
+
+    /// Whether `directory` is inside the project's root or one of its worktrees
+    pub fn contains(&self, directory: &str) -> bool {
+        let Ok(directory) = Path::new(directory).canonicalize() else {
+            return false;
+        };
+        self.directories()
+            .filter_map(|root| Path::new(root).canonicalize().ok())
+            .any(|root| directory.starts_with(root))
+    }
+
+    /// Fail with `InvalidInput` unless `directory` lies within the project
+    pub fn check_directory(&self, directory: &str) -> Result<()> {
+        if self.contains(directory) {
+            return Ok(());
+        }
+        Err(ForgeError::new(
+            ErrorCode::InvalidInput,
+            format!("{} is not inside project {}", directory, self.name),
+        )
+        .with_details(serde_json::json!({
+            "projectId": self.id,
+            "directory": directory,
+            "rootPath": self.root_path,
+        }))
+        .into())
+    }
+
+    /// Directory for a new worktree on `branch`
+    pub fn worktree_path(&self, branch: &str) -> PathBuf {
+        let dir = match &self.settings.worktree_dir {
+            Some(dir) => Path::new(&self.root_path).join(dir),
+            None => {
+                let root = Path::new(&self.root_path);
+                let name = root.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                root.with_file_name(format!("{}-worktrees", name))
+            }
+        };
+        dir.join(branch.replace('/', "-"))
+    }
+}
@@ -0,0 +1,455 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::types::{SearchDocument, SearchHit, SearchQuery};
+
+const DEFAULT_LIMIT: usize = 50;
+/// Characters of context on each side of the first match
+const SNIPPET_RADIUS: usize = 80;
+/// Longer tokens are usually hashes or encoded data, not words anyone searches for
+const MAX_TOKEN_LEN: usize = 64;
+/// Weight of a term that only matches as a prefix, e.g. "auth" for "authentication"
+const PREFIX_WEIGHT: f64 = 0.5;
+// BM25 parameters
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+type DocId = u64;
+
+struct IndexedDocument {
+    document: SearchDocument,
+    terms: HashMap<String, u32>,
+    length: usize,
+}
+
+#[derive(Default)]
+struct IndexedSession {
+    documents: Vec<DocId>,
+    /// Number of session messages indexed so far
+    messages: usize,
+}
+
+/// Inverted index over session transcripts
+#[derive(Default)]
+pub struct SearchIndex {
+    next_id: DocId,
+    documents: HashMap<DocId, IndexedDocument>,
+    /// Term -> document -> occurrences
+    postings: BTreeMap<String, HashMap<DocId, u32>>,
+    sessions: HashMap<String, IndexedSession>,
+    total_length: usize,
+}
+
+/// Words of `text` with their byte offsets
+fn tokens(text: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        if c.is_alphanumeric() || c == '_' {
+            start.get_or_insert(i);
+        } else if let Some(s) = start.take() {
+            tokens.push((s, &text[s..i]));
+        }
+    }
+    if let Some(s) = start {
+        tokens.push((s, &text[s..]));
+    }
+    tokens
+}
+
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    tokens(text)
+        .into_iter()
+        .filter(|(_, token)| token.chars().count() <= MAX_TOKEN_LEN)
+        .map(|(_, token)| token.to_lowercase())
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of a session's messages already in the index
+    pub fn indexed_messages(&self, session_id: &str) -> usize {
+        self.sessions.get(session_id).map_or(0, |session| session.messages)
+    }
+
+    /// Session ids with indexed messages
+    pub fn session_ids(&self) -> Vec<String> {
+        self.sessions.keys().cloned().collect()
+    }
+
+    /// Index the next message of a session
+    pub fn add(&mut self, document: SearchDocument) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let session = self.sessions.entry(document.session_id.clone()).or_default();
+        session.documents.push(id);
+        session.messages += 1;
+
+        self.insert(id, document);
+    }
+
+    /// Append the next message of a session to its last document if that has the same role
+    /// and is not a tool call, e.g. a streamed reply chunk. Otherwise index it on its own.
+    pub fn extend_last(&mut self, document: SearchDocument) {
+        let last = self
+            .sessions
+            .get(&document.session_id)
+            .and_then(|session| session.documents.last())
+            .filter(|id| {
+                self.documents.get(id).is_some_and(|indexed| {
+                    indexed.document.role == document.role && indexed.document.tool.is_none()
+                })
+            })
+            .copied();
+        let Some((id, mut existing)) = last.and_then(|id| Some((id, self.remove(id)?))) else {
+            self.add(document);
+            return;
+        };
+
+        existing.content.push_str(&document.content);
+        if let Some(session) = self.sessions.get_mut(&document.session_id) {
+            session.messages += 1;
+        }
+        self.insert(id, existing);
+    }
+
+    pub fn remove_session(&mut self, session_id: &str) {
+        if let Some(session) = self.sessions.remove(session_id) {
+            for id in session.documents {
+                self.remove(id);
+            }
+        }
+    }
+
+    fn insert(&mut self, id: DocId, document: SearchDocument) {
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        let fields = [
+            Some(document.content.as_str()),
+            document.tool.as_deref(),
+            document.tool_input.as_deref(),
+            Some(document.agent_id.as_str()),
+        ];
+        for field in fields.into_iter().flatten() {
+            for term in terms(field) {
+                *counts.entry(term).or_default() += 1;
+            }
+        }
+
+        let length = counts.values().sum::<u32>() as usize;
+        for (term, count) in &counts {
+            self.postings.entry(term.clone()).or_default().insert(id, *count);
+        }
+        self.total_length += length;
+        self.documents.insert(
+            id,
+            IndexedDocument {
+                document,
+                terms: counts,
+                length,
+            },
+        );
+    }
+
+    fn remove(&mut self, id: DocId) -> Option<SearchDocument> {
+        let indexed = self.documents.remove(&id)?;
+        for term in indexed.terms.keys() {
+            if let Some(postings) = self.postings.get_mut(term) {
+                postings.remove(&id);
+                if postings.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        self.total_length -= indexed.length;
+        Some(indexed.document)
+    }
+
+    /// Ranked hits for a query. An empty query lists everything matching the filters, newest first.
+    pub fn search(&self, query: &SearchQuery) -> Vec<SearchHit> {
+        let query_terms: Vec<String> = terms(&query.query).collect();
+
+        let mut scores: HashMap<DocId, f64> = HashMap::new();
+        if query_terms.is_empty() {
+            scores.extend(self.documents.keys().map(|id| (*id, 0.0)));
+        } else {
+            let document_count = self.documents.len() as f64;
+            let average_length = (self.total_length as f64 / document_count.max(1.0)).max(1.0);
+
+            for (position, query_term) in query_terms.iter().enumerate() {
+                let mut term_scores: HashMap<DocId, f64> = HashMap::new();
+                let matches = self
+                    .postings
+                    .range(query_term.clone()..)
+                    .take_while(|(term, _)| term.starts_with(query_term.as_str()));
+
+                for (term, postings) in matches {
+                    let weight = if term == query_term { 1.0 } else { PREFIX_WEIGHT };
+                    let frequency = postings.len() as f64;
+                    let idf = (1.0 + (document_count - frequency + 0.5) / (frequency + 0.5)).ln();
+
+                    for (id, count) in postings {
+                        let length = self.documents[id].length as f64;
+                        let tf = *count as f64;
+                        let score =
+                            idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * length / average_length));
+                        *term_scores.entry(*id).or_default() += weight * score;
+                    }
+                }
+
+                // Every query term has to match
+                if position == 0 {
+                    scores = term_scores;
+                } else {
+                    scores.retain(|id, _| term_scores.contains_key(id));
+                    for (id, score) in scores.iter_mut() {
+                        *score += term_scores[id];
+                    }
+                }
+            }
+        }
+
+        let mut hits: Vec<SearchHit> = scores
+            .into_iter()
+            .filter_map(|(id, score)| {
+                let document = &self.documents.get(&id)?.document;
+                matches_filters(document, query).then(|| SearchHit {
+                    session_id: document.session_id.clone(),
+                    agent_id: document.agent_id.clone(),
+                    message_index: document.message_index,
+                    role: document.role.clone(),
+                    tool: document.tool.clone(),
+                    working_directory: document.working_directory.clone(),
+                    timestamp: document.timestamp,
+                    score,
+                    snippet: snippet(document, &query_terms),
+                })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| b.timestamp.cmp(&a.timestamp))
+        });
+        hits.truncate(query.limit.unwrap_or(DEFAULT_LIMIT));
+        hits
+    }
+}
+
+fn matches_filters(document: &SearchDocument, query: &SearchQuery) -> bool {
+    if query.agent_id.as_ref().is_some_and(|agent_id| *agent_id != document.agent_id) {
+        return false;
+    }
+    if query.from.is_some_and(|from| document.timestamp < from) {
+        return false;
+    }
+    if query.to.is_some_and(|to| document.timestamp > to) {
+        return false;
+    }
+    if let Some(directory) = &query.working_directory {
+        let inside = document
+            .working_directory
+            .as_deref()
+            .is_some_and(|cwd| std::path::Path::new(cwd).starts_with(directory));
+        if !inside {
+            return false;
+        }
+    }
+    if let Some(tool) = &query.tool {
+        if !document.tool.as_deref().is_some_and(|name| name.eq_ignore_ascii_case(tool)) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Text around the first query term, from the message or else the tool input
+fn snippet(document: &SearchDocument, query_terms: &[String]) -> String {
+    let fields = [Some(document.content.as_str()), document.tool_input.as_deref()];
+    for text in fields.into_iter().flatten() {
+        let first_match = tokens(text).into_iter().find(|(_, token)| {
+            let token = token.to_lowercase();
+            query_terms.iter().any(|term| token.starts_with(term.as_str()))
+        });
+        if let Some((offset, _)) = first_match {
+            return excerpt(text, offset);
+        }
+    }
+    excerpt(&document.content, 0)
+}
+
+fn excerpt(text: &str, offset: usize) -> String {
+    let before: Vec<(usize, char)> = text[..offset].char_indices().collect();
+    let start = before
+        .len()
+        .checked_sub(SNIPPET_RADIUS)
+        .map_or(0, |i| before[i].0);
+    let end = text[offset..]
+        .char_indices()
+        .nth(SNIPPET_RADIUS)
+        .map_or(text.len(), |(i, _)| offset + i);
+
+    let mut excerpt = text[start..end].split_whitespace().collect::<Vec<_>>().join(" ");
+    if start > 0 {
+        excerpt.insert_str(0, "...");
+    }
+    if end < text.len() {
+        excerpt.push_str("...");
+    }
+    excerpt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn document(session_id: &str, message_index: usize, role: &str, content: &str) -> SearchDocument {
+        SearchDocument {
+            session_id: session_id.to_string(),
+            agent_id: "agent".to_string(),
+            message_index,
+            role: role.to_string(),
+            content: content.to_string(),
+            tool: None,
+            tool_input: None,
+            working_directory: Some("/repo".to_string()),
+            timestamp: Utc.timestamp_opt(1_760_000_000 + message_index as i64, 0).unwrap(),
+        }
+    }
+
+    fn query(text: &str) -> SearchQuery {
+        SearchQuery {
+            query: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn found(index: &SearchIndex, query: &SearchQuery) -> Vec<usize> {
+        index.search(query).into_iter().map(|hit| hit.message_index).collect()
+    }
+
+    #[test]
+    fn exact_matches_rank_above_prefix_matches() {
+        let mut index = SearchIndex::new();
+        index.add(document("s", 0, "user", "add authentication to the API"));
+        index.add(document("s", 1, "user", "the auth middleware rejects tokens"));
+        index.add(document("s", 2, "user", "unrelated message"));
+
+        assert_eq!(found(&index, &query("auth")), [1, 0]);
+    }
+
+    #[test]
+    fn every_term_has_to_match() {
+        let mut index = SearchIndex::new();
+        index.add(document("s", 0, "user", "fix the login form"));
+        index.add(document("s", 1, "user", "fix the build"));
+
+        assert_eq!(found(&index, &query("FIX login")), [0]);
+        assert!(found(&index, &query("fix deploy")).is_empty());
+    }
+
+    #[test]
+    fn empty_query_lists_everything_newest_first() {
+        let mut index = SearchIndex::new();
+        index.add(document("s", 0, "user", "first"));
+        index.add(document("s", 1, "assistant", "second"));
+
+        assert_eq!(found(&index, &query("")), [1, 0]);
+        assert_eq!(found(&index, &query("  ...  ")), [1, 0]);
+        assert!(SearchIndex::new().search(&query("")).is_empty());
+    }
+
+    #[test]
+    fn filters_apply_to_ranked_hits() {
+        let mut index = SearchIndex::new();
+        let mut read = document("s", 0, "system", "Read file");
+        read.tool = Some("Read".to_string());
+        read.tool_input = Some("src/main.rs".to_string());
+        index.add(read);
+        let mut elsewhere = document("s", 1, "user", "main file");
+        elsewhere.working_directory = Some("/repository".to_string());
+        index.add(elsewhere);
+
+        let by_tool = SearchQuery { tool: Some("read".to_string()), ..query("main") };
+        assert_eq!(found(&index, &by_tool), [0]);
+        let by_directory = SearchQuery { working_directory: Some("/repo".to_string()), ..query("file") };
+        assert_eq!(found(&index, &by_directory), [0]);
+        let by_agent = SearchQuery { agent_id: Some("other".to_string()), ..query("file") };
+        assert!(found(&index, &by_agent).is_empty());
+    }
+
+    #[test]
+    fn streamed_chunks_extend_the_last_document() {
+        let mut index = SearchIndex::new();
+        index.add(document("s", 0, "assistant", "Refactor"));
+        index.extend_last(document("s", 1, "assistant", "ing the parser"));
+        index.extend_last(document("s", 2, "user", "thanks"));
+
+        assert_eq!(index.indexed_messages("s"), 3);
+        assert_eq!(found(&index, &query("refactoring")), [0]);
+        assert_eq!(found(&index, &query("thanks")), [2]);
+    }
+
+    #[test]
+    fn removed_sessions_are_not_found() {
+        let mut index = SearchIndex::new();
+        index.add(document("a", 0, "user", "shared words"));
+        index.add(document("b", 0, "user", "shared words"));
+        index.remove_session("a");
+
+        let hits = index.search(&query("shared"));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session_id, "b");
+        assert_eq!(index.session_ids(), ["b"]);
+        assert_eq!(index.indexed_messages("a"), 0);
+    }
+
+    #[test]
+    fn overlong_tokens_are_not_indexed() {
+        let mut index = SearchIndex::new();
+        let hash = "a".repeat(MAX_TOKEN_LEN + 1);
+        index.add(document("s", 0, "user", &format!("commit {}", hash)));
+
+        assert!(found(&index, &query("aaaa")).is_empty());
+        assert_eq!(found(&index, &query("commit")), [0]);
+    }
+
+    #[test]
+    fn multibyte_text_is_matched_case_insensitively() {
+        let mut index = SearchIndex::new();
+        index.add(document("s", 0, "user", "Überprüfe die ÉCOLE-Tabelle 日本語"));
+
+        assert_eq!(found(&index, &query("überprüfe")), [0]);
+        assert_eq!(found(&index, &query("école")), [0]);
+        assert_eq!(found(&index, &query("日本語")), [0]);
+    }
+
+    #[test]
+    fn snippets_surround_the_first_match() {
+        let mut index = SearchIndex::new();
+        let text = format!("{} needle {}", "é ".repeat(100), "ü ".repeat(100));
+        index.add(document("s", 0, "user", &text));
+
+        let snippet = &index.search(&query("needle"))[0].snippet;
+        assert!(snippet.starts_with("..."));
+        assert!(snippet.ends_with("..."));
+        assert!(snippet.contains("needle"));
+        assert!(snippet.chars().count() <= 2 * SNIPPET_RADIUS + 6);
+    }
+
+    #[test]
+    fn snippets_fall_back_to_the_tool_input() {
+        let mut index = SearchIndex::new();
+        let mut edit = document("s", 0, "system", "Edit");
+        edit.tool = Some("Edit".to_string());
+        edit.tool_input = Some("src/lib.rs".to_string());
+        index.add(edit);
+        index.add(document("s", 1, "user", "short\n\ttext"));
+
+        assert_eq!(index.search(&query("lib"))[0].snippet, "src/lib.rs");
+        assert_eq!(index.search(&query("short"))[0].snippet, "short text");
+    }
+}
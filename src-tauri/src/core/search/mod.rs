@@ -0,0 +1,5 @@
+pub mod index;
+pub mod types;
+
+pub use index::SearchIndex;
+pub use types::{SearchDocument, SearchHit, SearchQuery};
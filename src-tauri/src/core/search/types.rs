@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A searchable unit: one user prompt, assistant reply or tool call
+#[derive(Debug, Clone)]
+pub struct SearchDocument {
+    pub session_id: String,
+    pub agent_id: String,
+    /// Index of the first session message the document covers
+    pub message_index: usize,
+    pub role: String,
+    pub content: String,
+    pub tool: Option<String>,
+    /// File paths, commands and other string arguments of the tool call
+    pub tool_input: Option<String>,
+    pub working_directory: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchQuery {
+    pub query: String,
+    pub agent_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Only sessions working in this directory or below it
+    pub working_directory: Option<String>,
+    pub tool: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub session_id: String,
+    pub agent_id: String,
+    pub message_index: usize,
+    pub role: String,
+    pub tool: Option<String>,
+    pub working_directory: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub score: f64,
+    pub snippet: String,
+}
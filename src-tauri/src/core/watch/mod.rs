@@ -0,0 +1,5 @@
+pub mod types;
+pub mod watcher;
+
+pub use types::{FilesChanged, GitStatusChanged, WatchEvent};
+pub use watcher::DirectoryWatchers;
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::git::GitStatus;
+
+/// Files that changed in a watched worktree, sent as `files-changed`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilesChanged {
+    /// Top directory of the watched worktree
+    pub directory: String,
+    pub project_id: Option<String>,
+    /// Absolute paths, leaving out `.git` and ignored files
+    pub paths: Vec<String>,
+}
+
+/// A watched worktree's new git status, sent as `git-status-changed` when it differs from the last one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatusChanged {
+    pub directory: String,
+    pub project_id: Option<String>,
+    pub status: GitStatus,
+}
+
+#[derive(Debug, Clone)]
+pub enum WatchEvent {
+    FilesChanged(FilesChanged),
+    GitStatusChanged(GitStatusChanged),
+}
@@ -0,0 +1,376 @@
+use anyhow::Result;
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::{debug, info, warn, Instrument};
+
+use super::types::{FilesChanged, GitStatusChanged, WatchEvent};
+use crate::core::git::{self, GitStatus};
+
+/// Changes are sent once the worktree has been quiet this long...
+const QUIET_PERIOD: Duration = Duration::from_millis(300);
+/// ...or this long after the first one, whichever comes first
+const MAX_DELAY: Duration = Duration::from_secs(2);
+/// How long a worktree stays watched after its last session stops, for changes still in flight
+const LINGER: Duration = Duration::from_secs(1);
+
+type Sink = Arc<dyn Fn(WatchEvent) + Send + Sync>;
+
+struct Watch {
+    /// Dropping the watcher ends the task forwarding its changes, which only holds a weak
+    /// reference to add directories created later
+    _watcher: Arc<Mutex<RecommendedWatcher>>,
+    sessions: HashSet<String>,
+}
+
+#[derive(Default)]
+struct Watches {
+    /// By worktree top directory
+    watches: HashMap<String, Watch>,
+    /// Worktree each session is watching
+    sessions: HashMap<String, String>,
+}
+
+/// Watches the worktrees sessions run in, one watcher per worktree however many sessions share it.
+/// Each directory is watched on its own so ignored ones, like `node_modules`, cost nothing.
+pub struct DirectoryWatchers {
+    inner: Mutex<Watches>,
+    sink: Sink,
+}
+
+impl DirectoryWatchers {
+    /// Watchers sending their changes to `sink`
+    pub fn new(sink: impl Fn(WatchEvent) + Send + Sync + 'static) -> Self {
+        Self {
+            inner: Mutex::new(Watches::default()),
+            sink: Arc::new(sink),
+        }
+    }
+
+    /// Watch the worktree containing `directory` on behalf of a session, or `directory` itself
+    /// outside a repository. Moves the session's watch if it was watching somewhere else.
+    pub async fn watch(self: &Arc<Self>, session_id: &str, directory: &str, project_id: Option<String>) -> Result<()> {
+        let root = {
+            let directory = directory.to_string();
+            tokio::task::spawn_blocking(move || git::toplevel(&directory).unwrap_or(directory)).await?
+        };
+
+        // Listing the worktree's directories takes a while in a big one, so it runs unlocked.
+        // Loops in the rare case the worktree's watch lingered out while being looked at.
+        loop {
+            let watched = {
+                let inner = self.inner.lock().unwrap();
+                if inner.sessions.get(session_id) == Some(&root) {
+                    return Ok(());
+                }
+                inner.watches.contains_key(&root)
+            };
+            let started = if watched {
+                None
+            } else {
+                let root = root.clone();
+                Some(tokio::task::spawn_blocking(move || start_watcher(&root)).await??)
+            };
+
+            let mut inner = self.inner.lock().unwrap();
+            // Another session may have started watching the worktree in the meantime
+            if let (Some(started), false) = (started, inner.watches.contains_key(&root)) {
+                let watcher = Arc::new(Mutex::new(started.watcher));
+                let forward = forward_changes(
+                    root.clone(),
+                    project_id.clone(),
+                    started.git_dirs,
+                    Arc::downgrade(&watcher),
+                    started.events,
+                    self.sink.clone(),
+                );
+                tokio::spawn(forward.instrument(crate::span!("watch", directory = %root)));
+                inner.watches.insert(
+                    root.clone(),
+                    Watch {
+                        _watcher: watcher,
+                        sessions: HashSet::new(),
+                    },
+                );
+            }
+            if !inner.watches.contains_key(&root) {
+                continue;
+            }
+
+            self.release(&mut inner, session_id);
+            inner.watches.get_mut(&root).expect("watch was just checked").sessions.insert(session_id.to_string());
+            inner.sessions.insert(session_id.to_string(), root);
+            return Ok(());
+        }
+    }
+
+    /// Sessions that have a worktree watched
+    pub fn sessions(&self) -> Vec<String> {
+        self.inner.lock().unwrap().sessions.keys().cloned().collect()
+    }
+
+    /// Stop watching for a session; the worktree stays watched while other sessions use it
+    pub fn unwatch(self: &Arc<Self>, session_id: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        self.release(&mut inner, session_id);
+    }
+
+    fn release(self: &Arc<Self>, inner: &mut Watches, session_id: &str) {
+        let Some(root) = inner.sessions.remove(session_id) else {
+            return;
+        };
+        let Some(watch) = inner.watches.get_mut(&root) else {
+            return;
+        };
+        watch.sessions.remove(session_id);
+        if !watch.sessions.is_empty() {
+            return;
+        }
+
+        // Keep the watcher a moment longer unless a session picks the worktree back up
+        let watchers = Arc::clone(self);
+        tokio::spawn(async move {
+            tokio::time::sleep(LINGER).await;
+            let mut inner = watchers.inner.lock().unwrap();
+            if inner.watches.get(&root).is_some_and(|watch| watch.sessions.is_empty()) {
+                inner.watches.remove(&root);
+                info!("Stopped watching {}", root);
+            }
+        });
+    }
+}
+
+type Events = mpsc::UnboundedReceiver<notify::Result<notify::Event>>;
+
+/// A worktree's watcher before its changes are forwarded
+struct Started {
+    watcher: RecommendedWatcher,
+    events: Events,
+    git_dirs: Option<(PathBuf, PathBuf)>,
+}
+
+/// Watch every directory of the worktree at `root` that is not ignored, and its git directories
+fn start_watcher(root: &str) -> Result<Started> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })?;
+    let git_dirs = git::git_dirs(root);
+    let ignored = ignored_directories(root, git_dirs.is_some());
+    let directories = directories(Path::new(root), &ignored);
+    watcher.watch(Path::new(root), RecursiveMode::NonRecursive)?;
+    for directory in directories.iter().skip(1) {
+        if let Err(e) = watcher.watch(directory, RecursiveMode::NonRecursive) {
+            debug!("Cannot watch {}: {}", directory.display(), e);
+        }
+    }
+    // HEAD and the index are the worktree's own, refs are shared by all worktrees
+    if let Some((git_dir, common_dir)) = &git_dirs {
+        watcher.watch(git_dir, RecursiveMode::NonRecursive)?;
+        if common_dir != git_dir {
+            watcher.watch(common_dir, RecursiveMode::NonRecursive)?;
+        }
+        if let Err(e) = watcher.watch(&common_dir.join("refs"), RecursiveMode::Recursive) {
+            debug!("Cannot watch refs of {}: {}", root, e);
+        }
+    }
+    info!("Watching {} directories of {} for changes", directories.len(), root);
+    Ok(Started {
+        watcher,
+        events: rx,
+        git_dirs,
+    })
+}
+
+/// Collect raw events into debounced batches and send what they changed
+async fn forward_changes(
+    root: String,
+    project_id: Option<String>,
+    git_dirs: Option<(PathBuf, PathBuf)>,
+    watcher: Weak<Mutex<RecommendedWatcher>>,
+    mut rx: Events,
+    sink: Sink,
+) {
+    let mut last_status = {
+        let root = root.clone();
+        tokio::task::spawn_blocking(move || git::check_status(&root).ok())
+            .await
+            .ok()
+            .flatten()
+    };
+
+    while let Some(first) = rx.recv().await {
+        let mut changed = HashSet::new();
+        let mut created = HashSet::new();
+        collect(first, &mut changed, &mut created);
+
+        let deadline = Instant::now() + MAX_DELAY;
+        loop {
+            let wait = QUIET_PERIOD.min(deadline.saturating_duration_since(Instant::now()));
+            match tokio::time::timeout(wait, rx.recv()).await {
+                Ok(Some(event)) => collect(event, &mut changed, &mut created),
+                Ok(None) | Err(_) => break,
+            }
+        }
+        if changed.is_empty() {
+            continue;
+        }
+
+        let batch = {
+            let root = root.clone();
+            let git_dirs = git_dirs.clone();
+            let watcher = watcher.clone();
+            let last_status = last_status.clone();
+            tokio::task::spawn_blocking(move || {
+                if let Some(watcher) = watcher.upgrade() {
+                    watch_created(&root, git_dirs.is_some(), created, &watcher);
+                }
+                summarize(&root, git_dirs.as_ref(), changed, last_status)
+            })
+        };
+        let (paths, status) = match batch.await {
+            Ok(batch) => batch,
+            Err(e) => {
+                warn!("Failed to process file changes: {}", e);
+                continue;
+            }
+        };
+
+        if !paths.is_empty() {
+            debug!("{} files changed", paths.len());
+            sink(WatchEvent::FilesChanged(FilesChanged {
+                directory: root.clone(),
+                project_id: project_id.clone(),
+                paths,
+            }));
+        }
+        if let Some(status) = status {
+            last_status = Some(status.clone());
+            sink(WatchEvent::GitStatusChanged(GitStatusChanged {
+                directory: root.clone(),
+                project_id: project_id.clone(),
+                status,
+            }));
+        }
+    }
+}
+
+/// Add the paths an event changed, and those it created or moved into place
+fn collect(event: notify::Result<notify::Event>, changed: &mut HashSet<PathBuf>, created: &mut HashSet<PathBuf>) {
+    match event {
+        // Reads don't change anything
+        Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+        Ok(event) => {
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))) {
+                created.extend(event.paths.iter().cloned());
+            }
+            changed.extend(event.paths);
+        }
+        Err(e) => warn!("File watcher error: {}", e),
+    }
+}
+
+/// Start watching directories that appeared in the worktree, unless they are ignored
+fn watch_created(root: &str, in_repository: bool, created: HashSet<PathBuf>, watcher: &Mutex<RecommendedWatcher>) {
+    let created: Vec<PathBuf> = created
+        .into_iter()
+        .filter(|path| !path.components().any(|component| component == Component::Normal(".git".as_ref())))
+        .filter(|path| fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir()))
+        .collect();
+    if created.is_empty() {
+        return;
+    }
+
+    let ignored = ignored_directories(root, in_repository);
+    let mut watcher = watcher.lock().unwrap();
+    for start in created.iter().filter(|path| !ignored.contains(*path)) {
+        for directory in directories(start, &ignored) {
+            match watcher.watch(&directory, RecursiveMode::NonRecursive) {
+                Ok(()) => debug!("Watching new directory {}", directory.display()),
+                Err(e) => debug!("Cannot watch {}: {}", directory.display(), e),
+            }
+        }
+    }
+}
+
+/// Ignored directories of the repository at `root`; nothing is ignored outside one
+fn ignored_directories(root: &str, in_repository: bool) -> HashSet<PathBuf> {
+    if !in_repository {
+        return HashSet::new();
+    }
+    git::ignored_directories(root).unwrap_or_else(|e| {
+        debug!("Not skipping ignored directories: {}", e);
+        HashSet::new()
+    })
+}
+
+/// `start` and the directories below it, leaving out `.git`, `ignored` and symlinked directories
+fn directories(start: &Path, ignored: &HashSet<PathBuf>) -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+    let mut pending = vec![start.to_path_buf()];
+    while let Some(directory) = pending.pop() {
+        // `file_type` doesn't follow symlinks
+        for entry in fs::read_dir(&directory).into_iter().flatten().flatten() {
+            if entry.file_type().is_ok_and(|kind| kind.is_dir()) && entry.file_name() != ".git" {
+                let path = entry.path();
+                if !ignored.contains(&path) {
+                    pending.push(path);
+                }
+            }
+        }
+        directories.push(directory);
+    }
+    directories
+}
+
+/// Changed files worth reporting, and the git status if it differs from `last_status`
+fn summarize(
+    root: &str,
+    git_dirs: Option<&(PathBuf, PathBuf)>,
+    changed: HashSet<PathBuf>,
+    last_status: Option<GitStatus>,
+) -> (Vec<String>, Option<GitStatus>) {
+    let mut git_changed = false;
+    let mut files = Vec::new();
+    for path in changed {
+        let internal = git_dirs.and_then(|(git_dir, common_dir)| {
+            path.strip_prefix(git_dir).or_else(|_| path.strip_prefix(common_dir)).ok()
+        });
+        if let Some(internal) = internal {
+            git_changed |= affects_status(internal);
+        } else if !path.components().any(|component| component == Component::Normal(".git".as_ref())) {
+            files.push(path);
+        }
+    }
+
+    // Outside a repository nothing is ignored
+    match git::ignored_paths(root, &files) {
+        Ok(ignored) => files.retain(|path| !ignored.contains(path)),
+        Err(e) => debug!("Not filtering ignored files: {}", e),
+    }
+    files.sort();
+
+    let status = if !files.is_empty() || git_changed {
+        git::check_status(root).ok().filter(|status| last_status.as_ref() != Some(status))
+    } else {
+        None
+    };
+    let paths = files.into_iter().map(|path| path.to_string_lossy().into_owned()).collect();
+    (paths, status)
+}
+
+/// Whether a change inside `.git` can change the status: HEAD, the index and refs, but not
+/// objects, logs or lock files
+fn affects_status(internal: &Path) -> bool {
+    let skipped = matches!(
+        internal.components().next(),
+        Some(Component::Normal(first)) if first == "objects" || first == "logs"
+    );
+    !skipped && internal.extension() != Some("lock".as_ref())
+}
@@ -0,0 +1,196 @@
+use anyhow::Result;
+use std::io::Read;
+use std::path::PathBuf;
+
+use crate::core::claude::QueryOptions;
+use crate::core::error::{ErrorCode, ForgeError};
+
+pub const USAGE: &str = "\
+Run Forge agents from the terminal, printing session events to stdout as NDJSON
+
+Usage:
+  forge-cli run [OPTIONS] <PROMPT>...    Run one prompt (`-` reads it from stdin)
+  forge-cli pipeline [OPTIONS] <FILE>    Run the steps of a JSON pipeline in order
+
+Options:
+  --agent <FILE>               Agent config (JSON) for steps that don't name their own
+  --template <ID|FILE>         Agent template (built-in id, or a .toml/.json file) instead of --agent
+  --cwd <DIR>                  Working directory [default: current directory]
+  --model <MODEL>              Model to use
+  --fallback-model <MODEL>     Model to retry with when the first one is overloaded
+  --allowed-tools <TOOLS>      Comma-separated tools the agent may use
+  --disallowed-tools <TOOLS>   Comma-separated tools the agent may not use
+  --mcp-config <FILE>          MCP server registry (mcp-servers.json from the app's config directory).
+                               Steps get the servers enabled for their agent's id in the app; it
+                               is an error if an agent has none, unless --mcp-servers is given
+  --mcp-servers <NAMES>        Comma-separated registry servers to enable for every step's agent
+  -h, --help                   Print this help
+
+Exit status is 0 when every step completed, 1 when one failed, 2 for bad arguments
+or configuration and 130 when interrupted.
+";
+
+pub enum Command {
+    Run { prompt: String, flags: Flags },
+    Pipeline { path: PathBuf, flags: Flags },
+    Help,
+}
+
+/// Options given on the command line, applied over the agent's own settings
+#[derive(Default)]
+pub struct Flags {
+    pub agent: Option<PathBuf>,
+    pub template: Option<String>,
+    pub mcp_config: Option<PathBuf>,
+    /// Registry servers to enable for every agent, on top of the ones enabled in the app
+    pub mcp_servers: Option<Vec<String>>,
+    pub options: QueryOptions,
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command> {
+    let mut args = args.into_iter();
+    let subcommand = match args.next() {
+        Some(arg) if arg == "-h" || arg == "--help" => return Ok(Command::Help),
+        Some(arg) => arg,
+        None => return Err(invalid("Missing command").into()),
+    };
+
+    let mut flags = Flags::default();
+    let mut positional = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| invalid(format!("Missing value for {}", arg)));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--agent" => flags.agent = Some(value()?.into()),
+            "--template" => flags.template = Some(value()?),
+            "--mcp-config" => flags.mcp_config = Some(value()?.into()),
+            "--mcp-servers" => flags.mcp_servers = Some(split_list(&value()?)),
+            "--cwd" => flags.options.cwd = Some(value()?),
+            "--model" => flags.options.model = Some(value()?),
+            "--fallback-model" => flags.options.fallback_model = Some(value()?),
+            "--allowed-tools" => flags.options.allowed_tools = Some(split_list(&value()?)),
+            "--disallowed-tools" => flags.options.disallowed_tools = Some(split_list(&value()?)),
+            "--" => positional.extend(args.by_ref()),
+            _ if arg.starts_with("--") => return Err(invalid(format!("Unknown option {}", arg)).into()),
+            _ => positional.push(arg),
+        }
+    }
+
+    if flags.agent.is_some() && flags.template.is_some() {
+        return Err(invalid("Use either --agent or --template").into());
+    }
+    if flags.mcp_servers.is_some() && flags.mcp_config.is_none() {
+        return Err(invalid("--mcp-servers needs --mcp-config").into());
+    }
+
+    match subcommand.as_str() {
+        "run" => {
+            let prompt = match positional.as_slice() {
+                [] => return Err(invalid("Missing prompt").into()),
+                [dash] if dash == "-" => {
+                    let mut prompt = String::new();
+                    std::io::stdin().read_to_string(&mut prompt)?;
+                    prompt
+                }
+                words => words.join(" "),
+            };
+            if prompt.trim().is_empty() {
+                return Err(invalid("Prompt is empty").into());
+            }
+            Ok(Command::Run { prompt, flags })
+        }
+        "pipeline" => match <[String; 1]>::try_from(positional) {
+            Ok([path]) => Ok(Command::Pipeline { path: path.into(), flags }),
+            Err(_) => Err(invalid("Expected exactly one pipeline file").into()),
+        },
+        other => Err(invalid(format!("Unknown command {}", other)).into()),
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn invalid(message: impl Into<String>) -> ForgeError {
+    ForgeError::new(ErrorCode::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Command> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    /// Message of the usage error `args` fail with
+    fn error(args: &[&str]) -> String {
+        let error = parse_args(args).err().expect("arguments should be rejected");
+        let error = error.downcast::<ForgeError>().expect("a ForgeError");
+        assert_eq!(error.code, ErrorCode::InvalidInput);
+        error.message
+    }
+
+    #[test]
+    fn run_joins_the_prompt_words() {
+        let Ok(Command::Run { prompt, flags }) = parse_args(&["run", "--model", "opus", "fix", "the", "build"]) else {
+            panic!("expected a run");
+        };
+        assert_eq!(prompt, "fix the build");
+        assert_eq!(flags.options.model.as_deref(), Some("opus"));
+    }
+
+    #[test]
+    fn options_after_double_dash_are_prompt_text() {
+        let Ok(Command::Run { prompt, flags }) = parse_args(&["run", "--", "--model", "ünïcode ✓"]) else {
+            panic!("expected a run");
+        };
+        assert_eq!(prompt, "--model ünïcode ✓");
+        assert_eq!(flags.options.model, None);
+    }
+
+    #[test]
+    fn tool_lists_are_split_on_commas() {
+        let Ok(Command::Run { flags, .. }) =
+            parse_args(&["run", "--allowed-tools", "Read, Grep,,", "--disallowed-tools", "", "go"])
+        else {
+            panic!("expected a run");
+        };
+        assert_eq!(flags.options.allowed_tools, Some(vec!["Read".to_string(), "Grep".to_string()]));
+        assert_eq!(flags.options.disallowed_tools, Some(Vec::new()));
+    }
+
+    #[test]
+    fn pipeline_takes_one_file() {
+        let Ok(Command::Pipeline { path, flags }) = parse_args(&["pipeline", "steps.json", "--cwd", "/repo"]) else {
+            panic!("expected a pipeline");
+        };
+        assert_eq!(path, PathBuf::from("steps.json"));
+        assert_eq!(flags.options.cwd.as_deref(), Some("/repo"));
+        assert_eq!(error(&["pipeline"]), "Expected exactly one pipeline file");
+        assert_eq!(error(&["pipeline", "a.json", "b.json"]), "Expected exactly one pipeline file");
+    }
+
+    #[test]
+    fn help_wins_anywhere() {
+        assert!(matches!(parse_args(&["--help"]), Ok(Command::Help)));
+        assert!(matches!(parse_args(&["run", "-h", "--bogus"]), Ok(Command::Help)));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(error(&[]), "Missing command");
+        assert_eq!(error(&["deploy"]), "Unknown command deploy");
+        assert_eq!(error(&["run", "--verbose", "go"]), "Unknown option --verbose");
+        assert_eq!(error(&["run", "go", "--model"]), "Missing value for --model");
+        assert_eq!(error(&["run"]), "Missing prompt");
+        assert_eq!(error(&["run", " ", ""]), "Prompt is empty");
+        assert_eq!(error(&["run", "--agent", "a.json", "--template", "t", "go"]), "Use either --agent or --template");
+        assert_eq!(error(&["run", "--mcp-servers", "github", "go"]), "--mcp-servers needs --mcp-config");
+    }
+}
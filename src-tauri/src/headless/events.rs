@@ -0,0 +1,54 @@
+use serde::Serialize;
+use std::io::Write;
+
+use crate::core::claude::{Message, SessionState, SessionStateChanged};
+use crate::core::error::ErrorResponse;
+use crate::core::git::GitStatus;
+
+/// One line of the headless CLI's output
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Event {
+    StepStarted {
+        step: usize,
+        name: Option<String>,
+        session_id: String,
+        agent_id: String,
+        prompt: String,
+    },
+    /// The same messages the app shows, including the prompt
+    Message(Box<Message>),
+    StateChanged(SessionStateChanged),
+    GitStatus {
+        directory: String,
+        status: GitStatus,
+    },
+    StepFinished {
+        step: usize,
+        session_id: String,
+        state: SessionState,
+        success: bool,
+    },
+    Error {
+        step: Option<usize>,
+        #[serde(flatten)]
+        error: ErrorResponse,
+    },
+    /// Always the last line
+    Finished {
+        success: bool,
+        steps_run: usize,
+        failed_steps: Vec<usize>,
+    },
+}
+
+impl Event {
+    /// Write the event to stdout as a single JSON line
+    pub fn emit(&self) {
+        let mut stdout = std::io::stdout().lock();
+        // A closed stdout only means nobody is reading anymore
+        let _ = serde_json::to_writer(&mut stdout, self);
+        let _ = writeln!(stdout);
+        let _ = stdout.flush();
+    }
+}
@@ -0,0 +1,314 @@
+mod args;
+mod events;
+mod pipeline;
+mod worktrees;
+
+use std::collections::HashMap;
+use std::process::ExitCode;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{info, warn};
+
+use crate::core::claude::{AgentConfig, ClaudeManager, QueryOptions, SessionState, SessionStateChanged};
+use crate::core::error::{ErrorCode, ErrorResponse, ForgeError};
+use crate::core::git;
+use crate::core::mcp::McpRegistry;
+use crate::core::projects::ProjectRegistry;
+use args::{Command, Flags};
+use events::Event;
+use pipeline::Pipeline;
+use worktrees::BranchWorktrees;
+
+const EXIT_FAILED: u8 = 1;
+/// Bad arguments or configuration; nothing ran
+const EXIT_USAGE: u8 = 2;
+const EXIT_INTERRUPTED: u8 = 130;
+
+enum StepResult {
+    Finished(SessionState),
+    Interrupted,
+}
+
+/// Entry point of the `forge-cli` binary
+pub fn run() -> ExitCode {
+    if let Err(e) = crate::core::logging::init_stderr() {
+        eprintln!("Failed to initialize logging: {}", e);
+    }
+
+    let (pipeline, flags) = match args::parse(std::env::args().skip(1)) {
+        Ok(Command::Help) => {
+            print!("{}", args::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Run { prompt, flags }) => (Pipeline::single(prompt), flags),
+        Ok(Command::Pipeline { path, flags }) => match Pipeline::load(&path) {
+            Ok(pipeline) => (pipeline, flags),
+            Err(e) => return usage_error(e),
+        },
+        Err(e) => {
+            eprintln!("forge-cli: {}\n\n{}", e, args::USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    tauri::async_runtime::block_on(run_pipeline(pipeline, flags))
+}
+
+async fn run_pipeline(pipeline: Pipeline, flags: Flags) -> ExitCode {
+    // Use the same MCP servers as the app when pointed at its registry
+    let mcp_registry = Arc::new(McpRegistry::new());
+    if let Some(path) = &flags.mcp_config {
+        if let Err(e) = mcp_registry.load(path.clone()) {
+            return usage_error(e);
+        }
+    }
+
+    let agent = match (&flags.agent, &flags.template) {
+        (Some(path), _) => pipeline::load_json::<AgentConfig>(path).map(Some),
+        (None, Some(template)) => pipeline::template_agent(template).map(Some),
+        (None, None) => Ok(pipeline.agent.clone()),
+    };
+    let default_agent = match agent {
+        Ok(agent) => agent.unwrap_or_else(pipeline::default_agent),
+        Err(e) => return usage_error(e),
+    };
+    if let Some(path) = &flags.mcp_config {
+        if let Err(e) = enable_mcp_servers(&mcp_registry, &pipeline, &default_agent, path, flags.mcp_servers.as_deref()) {
+            return usage_error(e);
+        }
+    }
+    let current_dir = std::env::current_dir()
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| ".".to_string());
+
+    let manager = ClaudeManager::new(mcp_registry, Arc::new(ProjectRegistry::new()));
+    let mut state_changes = manager.subscribe_state_changes();
+    // Steps of the same agent continue its conversation
+    let mut sessions: HashMap<String, String> = HashMap::new();
+    let mut failed_steps = Vec::new();
+    let mut steps_run = 0;
+    // Removed on every way out, interruptions included
+    let mut worktrees = BranchWorktrees::default();
+
+    for (step, spec) in pipeline.steps.iter().enumerate() {
+        let agent = spec.agent.as_ref().unwrap_or(&default_agent);
+        let mut options = pipeline::overlay(agent.query_options(), flags.options.clone());
+        if let Some(step_options) = spec.options.clone() {
+            options = pipeline::overlay(options, step_options);
+        }
+        let directory = options.cwd.get_or_insert_with(|| current_dir.clone()).clone();
+        // Scripts keep their own history; a step can still ask for checkpoints
+        options.checkpoint.get_or_insert(false);
+        steps_run += 1;
+
+        let session_id = match sessions.get(&agent.id) {
+            Some(session_id) => session_id.clone(),
+            None => match manager.create_session(&agent.id, None).await {
+                Ok(session_id) => {
+                    sessions.insert(agent.id.clone(), session_id.clone());
+                    session_id
+                }
+                Err(e) => {
+                    step_error(step, e);
+                    failed_steps.push(step);
+                    if pipeline.continue_on_error {
+                        continue;
+                    }
+                    break;
+                }
+            },
+        };
+
+        Event::StepStarted {
+            step,
+            name: spec.name.clone(),
+            session_id: session_id.clone(),
+            agent_id: agent.id.clone(),
+            prompt: spec.prompt.clone(),
+        }
+        .emit();
+
+        // Agents pinned to a branch run in a worktree of it
+        let directory = match &agent.branch {
+            Some(branch) => worktrees.directory(&directory, branch),
+            None => Ok(directory),
+        };
+        let (directory, result) = match directory {
+            Ok(directory) => {
+                options.cwd = Some(directory.clone());
+                let result = run_step(&manager, &session_id, step, &spec.prompt, options, &mut state_changes).await;
+                (directory, result)
+            }
+            Err(e) => {
+                step_error(step, e);
+                failed_steps.push(step);
+                if pipeline.continue_on_error {
+                    continue;
+                }
+                break;
+            }
+        };
+
+        let state = match result {
+            StepResult::Finished(state) => state,
+            StepResult::Interrupted => {
+                info!("Interrupted, aborted session {}", session_id);
+                failed_steps.push(step);
+                Event::Finished {
+                    success: false,
+                    steps_run,
+                    failed_steps,
+                }
+                .emit();
+                return ExitCode::from(EXIT_INTERRUPTED);
+            }
+        };
+
+        let success = state == SessionState::Completed;
+        Event::StepFinished {
+            step,
+            session_id,
+            state,
+            success,
+        }
+        .emit();
+
+        // Let scripts see what the step left behind
+        match git::check_status(&directory) {
+            Ok(status) => Event::GitStatus { directory, status }.emit(),
+            Err(e) => warn!("Failed to check git status of {}: {}", directory, e),
+        }
+
+        if !success {
+            failed_steps.push(step);
+            if !pipeline.continue_on_error {
+                break;
+            }
+        }
+    }
+
+    let success = failed_steps.is_empty();
+    Event::Finished {
+        success,
+        steps_run,
+        failed_steps,
+    }
+    .emit();
+
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(EXIT_FAILED)
+    }
+}
+
+/// Send one prompt and print everything the session reports until the query is over
+async fn run_step(
+    manager: &ClaudeManager,
+    session_id: &str,
+    step: usize,
+    prompt: &str,
+    options: QueryOptions,
+    state_changes: &mut broadcast::Receiver<SessionStateChanged>,
+) -> StepResult {
+    let (tx, mut rx) = mpsc::channel(100);
+    // Failures to start still report a diagnostic through `tx` before it is dropped
+    let started = manager.query(session_id, prompt, Some(options), tx).await;
+
+    let interrupt = tokio::signal::ctrl_c();
+    tokio::pin!(interrupt);
+
+    // The channel closes once the query's background task is done
+    loop {
+        tokio::select! {
+            // Transitions first, so they print before the messages they led to
+            biased;
+            Ok(change) = state_changes.recv() => Event::StateChanged(change).emit(),
+            message = rx.recv() => match message {
+                Some(message) => Event::Message(Box::new(message)).emit(),
+                None => break,
+            },
+            _ = &mut interrupt => {
+                if let Err(e) = manager.abort_session(session_id).await {
+                    warn!("Failed to abort session {}: {}", session_id, e);
+                }
+                drain_state_changes(state_changes);
+                return StepResult::Interrupted;
+            }
+        }
+    }
+    drain_state_changes(state_changes);
+
+    if let Err(e) = started {
+        step_error(step, e);
+        return StepResult::Finished(SessionState::Failed);
+    }
+    match manager.session_info(session_id).await {
+        Ok(info) => StepResult::Finished(info.state),
+        Err(e) => {
+            step_error(step, e);
+            StepResult::Finished(SessionState::Failed)
+        }
+    }
+}
+
+/// Print transitions that landed after the query's last message
+fn drain_state_changes(state_changes: &mut broadcast::Receiver<SessionStateChanged>) {
+    while let Ok(change) = state_changes.try_recv() {
+        Event::StateChanged(change).emit();
+    }
+}
+
+fn step_error(step: usize, error: anyhow::Error) {
+    Event::Error {
+        step: Some(step),
+        error: ErrorResponse::from(error),
+    }
+    .emit();
+}
+
+/// Enable `names` for the agent of every step, or make sure each of them already has servers
+/// enabled, so a registry given on the command line is never silently unused
+fn enable_mcp_servers(
+    registry: &McpRegistry,
+    pipeline: &Pipeline,
+    default_agent: &AgentConfig,
+    path: &std::path::Path,
+    names: Option<&[String]>,
+) -> anyhow::Result<()> {
+    let mut agent_ids: Vec<&str> = pipeline
+        .steps
+        .iter()
+        .map(|spec| spec.agent.as_ref().unwrap_or(default_agent).id.as_str())
+        .collect();
+    agent_ids.sort_unstable();
+    agent_ids.dedup();
+
+    for agent_id in agent_ids {
+        match names {
+            Some(names) => registry.enable_servers(agent_id, names)?,
+            None if registry.agent_servers(agent_id).is_empty() => {
+                return Err(ForgeError::new(
+                    ErrorCode::InvalidInput,
+                    format!(
+                        "No MCP servers in {} are enabled for agent {}; choose some with --mcp-servers",
+                        path.display(),
+                        agent_id
+                    ),
+                )
+                .into());
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+fn usage_error(error: anyhow::Error) -> ExitCode {
+    Event::Error {
+        step: None,
+        error: ErrorResponse::from(error),
+    }
+    .emit();
+    ExitCode::from(EXIT_USAGE)
+}
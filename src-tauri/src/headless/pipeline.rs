@@ -0,0 +1,123 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::core::agents::{read_template, AgentType, TemplateLibrary};
+use crate::core::claude::{AgentConfig, QueryOptions};
+use crate::core::error::{ErrorCode, ForgeError};
+
+/// Prompts run one after another; steps of the same agent continue one conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pipeline {
+    /// Agent for steps that don't name their own
+    pub agent: Option<AgentConfig>,
+    pub steps: Vec<PipelineStep>,
+    /// Keep going after a step fails
+    #[serde(default)]
+    pub continue_on_error: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineStep {
+    pub name: Option<String>,
+    pub prompt: String,
+    pub agent: Option<AgentConfig>,
+    /// Applied over the agent's settings and the command line
+    pub options: Option<QueryOptions>,
+}
+
+impl Pipeline {
+    pub fn single(prompt: String) -> Self {
+        Self {
+            agent: None,
+            steps: vec![PipelineStep {
+                name: None,
+                prompt,
+                agent: None,
+                options: None,
+            }],
+            continue_on_error: false,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let pipeline: Self = load_json(path)?;
+        if pipeline.steps.is_empty() {
+            return Err(ForgeError::new(ErrorCode::InvalidInput, "Pipeline has no steps")
+                .with_details(serde_json::json!({ "path": path }))
+                .into());
+        }
+        Ok(pipeline)
+    }
+}
+
+/// Agent used when neither the command line nor the pipeline configures one
+pub fn default_agent() -> AgentConfig {
+    AgentConfig {
+        id: "headless".to_string(),
+        name: "Headless".to_string(),
+        agent_type: AgentType::Task,
+        template: None,
+        system_prompt: None,
+        working_directory: None,
+        branch: None,
+        model: None,
+        max_tokens: None,
+        temperature: None,
+        max_turns: None,
+        allowed_tools: None,
+        disallowed_tools: None,
+        permission_mode: None,
+        retry_policy: None,
+    }
+}
+
+/// Agent from a template file, or a built-in template by id
+pub fn template_agent(template: &str) -> Result<AgentConfig> {
+    let path = Path::new(template);
+    let template = if path.is_file() {
+        read_template(path)?
+    } else {
+        TemplateLibrary::builtin()
+            .into_iter()
+            .find(|builtin| builtin.id == template)
+            .ok_or_else(|| {
+                ForgeError::new(ErrorCode::NotFound, format!("No built-in template or template file {:?}", template))
+            })?
+    };
+    Ok(template.agent_config(&template.id, None))
+}
+
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        ForgeError::new(ErrorCode::NotFound, format!("Failed to read {}: {}", path.display(), e))
+            .with_details(serde_json::json!({ "path": path }))
+    })?;
+    serde_json::from_str(&contents).map_err(|e| {
+        ForgeError::new(ErrorCode::InvalidInput, format!("Invalid {}: {}", path.display(), e))
+            .with_details(serde_json::json!({ "path": path }))
+            .into()
+    })
+}
+
+/// `over` wherever it sets a value, `base` everywhere else
+pub fn overlay(base: QueryOptions, over: QueryOptions) -> QueryOptions {
+    QueryOptions {
+        allowed_tools: over.allowed_tools.or(base.allowed_tools),
+        disallowed_tools: over.disallowed_tools.or(base.disallowed_tools),
+        max_thinking_tokens: over.max_thinking_tokens.or(base.max_thinking_tokens),
+        max_turns: over.max_turns.or(base.max_turns),
+        model: over.model.or(base.model),
+        fallback_model: over.fallback_model.or(base.fallback_model),
+        cwd: over.cwd.or(base.cwd),
+        custom_system_prompt: over.custom_system_prompt.or(base.custom_system_prompt),
+        append_system_prompt: over.append_system_prompt.or(base.append_system_prompt),
+        permission_mode: over.permission_mode.or(base.permission_mode),
+        retry_policy: over.retry_policy.or(base.retry_policy),
+        checkpoint: over.checkpoint.or(base.checkpoint),
+    }
+}
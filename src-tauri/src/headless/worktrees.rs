@@ -0,0 +1,58 @@
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::core::error::{ErrorCode, ForgeError};
+use crate::core::git;
+
+/// Worktrees for the branches agents are pinned to, so the user's checkout is never switched.
+/// The temporary ones are removed when this is dropped, unless they were left with changes.
+#[derive(Default)]
+pub struct BranchWorktrees {
+    /// Repository top directory and path of each worktree created here
+    created: Vec<(String, String)>,
+}
+
+impl BranchWorktrees {
+    /// Where to run on `branch` instead of in `directory`: the same place in the worktree the
+    /// branch is checked out in, which is created if there is none
+    pub fn directory(&mut self, directory: &str, branch: &str) -> Result<String> {
+        let root = git::toplevel(directory).ok_or_else(|| {
+            ForgeError::new(ErrorCode::NotAGitRepository, format!("{} is not in a git repository", directory))
+        })?;
+        let worktree = match git::checked_out_at(&root, branch)? {
+            Some(worktree) => worktree,
+            None => {
+                let name: String = branch
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                    .collect();
+                let suffix = &Uuid::new_v4().simple().to_string()[..8];
+                let path = std::env::temp_dir().join(format!("forge-{}-{}", name, suffix));
+                let path = path.to_string_lossy().into_owned();
+                git::checkout_worktree(&root, &path, branch)?;
+                self.created.push((root.clone(), path.clone()));
+                path
+            }
+        };
+
+        // Keep to the subdirectory the step was pointed at
+        let relative = fs::canonicalize(directory)
+            .ok()
+            .and_then(|directory| directory.strip_prefix(&root).ok().map(Path::to_path_buf))
+            .unwrap_or_default();
+        Ok(Path::new(&worktree).join(relative).to_string_lossy().into_owned())
+    }
+}
+
+impl Drop for BranchWorktrees {
+    fn drop(&mut self) {
+        for (root, path) in &self.created {
+            if let Err(e) = git::remove_worktree(root, path) {
+                warn!("Keeping worktree {}: {}", path, e);
+            }
+        }
+    }
+}
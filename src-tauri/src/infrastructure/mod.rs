@@ -1 +1,3 @@
-pub mod state;
\ No newline at end of file
+pub mod state;
+pub mod subscriptions;
+pub mod watchers;
@@ -1,14 +1,30 @@
+use crate::api::http::ControlServer;
 use crate::core::claude::ClaudeManager;
+use crate::core::mcp::McpRegistry;
+use crate::core::projects::ProjectRegistry;
 use std::sync::Arc;
 
+use super::subscriptions::SessionSubscriptions;
+
 pub struct AppState {
     pub claude_manager: Arc<ClaudeManager>,
+    pub mcp_registry: Arc<McpRegistry>,
+    pub project_registry: Arc<ProjectRegistry>,
+    pub subscriptions: Arc<SessionSubscriptions>,
+    pub control_server: Arc<ControlServer>,
 }
 
 impl AppState {
     pub fn new() -> Self {
+        let mcp_registry = Arc::new(McpRegistry::new());
+        let project_registry = Arc::new(ProjectRegistry::new());
+
         Self {
-            claude_manager: Arc::new(ClaudeManager::new()),
+            claude_manager: Arc::new(ClaudeManager::new(mcp_registry.clone(), project_registry.clone())),
+            mcp_registry,
+            project_registry,
+            subscriptions: Arc::new(SessionSubscriptions::new()),
+            control_server: Arc::new(ControlServer::new()),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,108 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+use tracing::warn;
+
+/// Events a slow listener outside the webview may fall behind by before it starts skipping
+const EVENT_CAPACITY: usize = 1024;
+
+/// A session event as windows receive it, for listeners outside the webview
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionEvent {
+    pub session_id: String,
+    pub event: String,
+    pub payload: Value,
+}
+
+impl SessionEvent {
+    pub fn new(session_id: &str, event: &str, payload: impl Serialize) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            event: event.to_string(),
+            payload: serde_json::to_value(payload).unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// Which windows follow which sessions, by window label
+pub struct SessionSubscriptions {
+    sessions: RwLock<HashMap<String, HashSet<String>>>,
+    events: broadcast::Sender<SessionEvent>,
+}
+
+impl Default for SessionSubscriptions {
+    fn default() -> Self {
+        Self {
+            sessions: RwLock::default(),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+}
+
+impl SessionSubscriptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&self, session_id: &str, window: &str) {
+        self.sessions
+            .write()
+            .unwrap()
+            .entry(session_id.to_string())
+            .or_default()
+            .insert(window.to_string());
+    }
+
+    pub fn unsubscribe(&self, session_id: &str, window: &str) {
+        let mut sessions = self.sessions.write().unwrap();
+        if let Some(windows) = sessions.get_mut(session_id) {
+            windows.remove(window);
+            if windows.is_empty() {
+                sessions.remove(session_id);
+            }
+        }
+    }
+
+    /// Forget a closed window
+    pub fn remove_window(&self, window: &str) {
+        let mut sessions = self.sessions.write().unwrap();
+        sessions.retain(|_, windows| {
+            windows.remove(window);
+            !windows.is_empty()
+        });
+    }
+
+    pub fn subscribers(&self, session_id: &str) -> Vec<String> {
+        self.sessions
+            .read()
+            .unwrap()
+            .get(session_id)
+            .map(|windows| windows.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Every session event, whichever windows follow the session
+    pub fn subscribe_events(&self) -> broadcast::Receiver<SessionEvent> {
+        self.events.subscribe()
+    }
+
+    /// Send a session's event to every window following it. Returns false if any window missed it.
+    pub fn emit<S: Serialize + Clone>(&self, app: &AppHandle, session_id: &str, event: &str, payload: S) -> bool {
+        if self.events.receiver_count() > 0 {
+            let _ = self.events.send(SessionEvent::new(session_id, event, &payload));
+        }
+
+        let mut delivered = true;
+        for window in self.subscribers(session_id) {
+            if let Err(e) = app.emit_to(window.as_str(), event, payload.clone()) {
+                warn!("Failed to emit {} to window {}: {}", event, window, e);
+                delivered = false;
+            }
+        }
+        delivered
+    }
+}
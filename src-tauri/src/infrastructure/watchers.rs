@@ -0,0 +1,64 @@
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::warn;
+
+use crate::core::claude::{ClaudeManager, SessionInfo, SessionState};
+use crate::core::watch::{DirectoryWatchers, WatchEvent};
+
+/// Watch the worktrees of running sessions, sending `files-changed` and
+/// `git-status-changed` to the frontend as agents edit them
+pub fn follow_sessions(app: AppHandle, claude_manager: Arc<ClaudeManager>) {
+    let watchers = Arc::new(DirectoryWatchers::new(move |event| {
+        let _ = match event {
+            WatchEvent::FilesChanged(changed) => app.emit("files-changed", changed),
+            WatchEvent::GitStatusChanged(changed) => app.emit("git-status-changed", changed),
+        };
+    }));
+
+    let mut state_changes = claude_manager.subscribe_state_changes();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match state_changes.recv().await {
+                // By the time the CLI runs the session's directory is settled
+                Ok(change) if change.state == SessionState::Running => {
+                    if let Ok(info) = claude_manager.session_info(&change.session_id).await {
+                        watch_session(&watchers, info).await;
+                    }
+                }
+                Ok(change) if !change.state.is_active() => watchers.unwatch(&change.session_id),
+                Ok(_) => {}
+                // Transitions were missed, so the watched sessions may be stale
+                Err(RecvError::Lagged(_)) => resync(&watchers, &claude_manager).await,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+async fn watch_session(watchers: &Arc<DirectoryWatchers>, info: SessionInfo) {
+    let Some(directory) = info.working_directory else {
+        return;
+    };
+    if let Err(e) = watchers.watch(&info.session_id, &directory, info.project_id).await {
+        warn!("Cannot watch {} for changes: {}", directory, e);
+    }
+}
+
+/// Watch the sessions running now and stop watching for those that have finished
+async fn resync(watchers: &Arc<DirectoryWatchers>, claude_manager: &ClaudeManager) {
+    let sessions = claude_manager.list_sessions().await;
+    for session_id in watchers.sessions() {
+        let active = sessions
+            .iter()
+            .any(|info| info.session_id == session_id && info.state.is_active());
+        if !active {
+            watchers.unwatch(&session_id);
+        }
+    }
+    for info in sessions {
+        if info.state == SessionState::Running {
+            watch_session(watchers, info).await;
+        }
+    }
+}
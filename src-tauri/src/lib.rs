@@ -1,15 +1,25 @@
 mod api;
-mod core;
+pub mod core;
+pub mod headless;
 mod infrastructure;
 
 use api::commands::{
-    abort_session, check_claude_auth, check_claude_cli, check_git_status, clear_session,
-    create_session, get_claude_cli_status, git_checkout_branch, git_create_branch,
-    git_stash_changes, greet, quick_claude_check, send_message,
+    abort_session, add_project_worktree, agent_from_template, check_claude_auth,
+    check_claude_cli, check_git_status, check_mcp_server, check_merge, clear_session,
+    create_log_bundle, create_session, export_agent_template, export_conversation, fork_session,
+    get_agent_mcp_servers, get_claude_cli_status, get_cli_parse_stats,
+    get_control_server_status, get_log_level, get_messages_since, get_project, get_session,
+    get_session_turns, git_checkout_branch, git_create_branch, git_stash_changes, greet,
+    import_agent_template, import_cli_session, import_conversation, list_agent_templates,
+    list_checkpoints, list_cli_sessions, list_mcp_servers, list_projects, list_recent_projects,
+    list_sessions, merge_branch, open_project, quick_claude_check, remove_agent_template,
+    remove_mcp_server, remove_project, remove_project_agent, restore_checkpoint,
+    save_mcp_server, save_project_agent, search_sessions, send_message, set_agent_mcp_servers,
+    set_log_level, start_control_server, stop_control_server, subscribe_logs, subscribe_session,
+    unsubscribe_logs, unsubscribe_session, update_project,
 };
 use infrastructure::state::AppState;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use tauri::{Emitter, Manager};
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // Initialize production-ready logging
@@ -24,26 +34,146 @@ pub fn run() {
     tracing::info!("Starting Forge application");
     tracing::debug!("Logging configuration: RUST_LOG={}", std::env::var("RUST_LOG").unwrap_or_else(|_| "not set".to_string()));
     
-    let app_state = Arc::new(Mutex::new(AppState::new()));
-
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
-        .manage(app_state)
+        .manage(AppState::new())
+        .setup(|app| {
+            // Keep rotating JSON log files in release builds, or when asked to in development
+            if !cfg!(debug_assertions) || std::env::var_os("FORGE_LOG_FILE").is_some() {
+                let log_dir = app.path().app_log_dir()?;
+                if let Err(e) = core::logging::enable_file_output(&log_dir, Default::default()) {
+                    tracing::error!("Failed to enable file logging: {}", e);
+                }
+            }
+
+            // Stream log records to the in-app log viewer
+            let handle = app.handle().clone();
+            let forwarded = core::logging::forward_records(move |window, record| {
+                let _ = handle.emit_to(window, "log-record", record);
+            });
+            if let Err(e) = forwarded {
+                tracing::warn!("Log viewer stream unavailable: {}", e);
+            }
+
+            // Announce session state transitions to the frontend
+            let handle = app.handle().clone();
+            let mut state_changes = app.state::<AppState>().claude_manager.subscribe_state_changes();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match state_changes.recv().await {
+                        Ok(change) => {
+                            let _ = handle.emit("session-state-changed", change);
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+
+            // Watch running sessions' worktrees so the frontend doesn't have to poll git
+            infrastructure::watchers::follow_sessions(
+                app.handle().clone(),
+                app.state::<AppState>().claude_manager.clone(),
+            );
+
+            // Load persisted MCP server definitions
+            let config_dir = app.path().app_config_dir()?;
+            let mcp_registry = app.state::<AppState>().mcp_registry.clone();
+            if let Err(e) = mcp_registry.load(config_dir.join("mcp-servers.json")) {
+                tracing::error!("Failed to load MCP servers: {}", e);
+            }
+            mcp_registry.set_runtime_dir(app.path().app_data_dir()?.join("mcp"));
+
+            // Load the projects the user has opened
+            let project_registry = app.state::<AppState>().project_registry.clone();
+            if let Err(e) = project_registry.load(config_dir.join("projects.json")) {
+                tracing::error!("Failed to load projects: {}", e);
+            }
+
+            // Start the control API if it was left on
+            match api::http::ControlServerConfig::load(&config_dir) {
+                Ok(config) if config.enabled => {
+                    let state = app.state::<AppState>();
+                    let context = api::http::ServerContext::new(app.handle().clone(), &state);
+                    let control_server = state.control_server.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = control_server.start(context, config.port, config.token).await {
+                            tracing::error!("Failed to start control server: {}", e);
+                        }
+                    });
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to read control server settings: {}", e),
+            }
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            // Stop routing session events and log records to closed windows
+            if let tauri::WindowEvent::Destroyed = event {
+                window.state::<AppState>().subscriptions.remove_window(window.label());
+                let _ = core::logging::set_stream_filter(window.label(), None);
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             create_session,
+            get_session,
+            list_sessions,
             send_message,
+            subscribe_session,
+            unsubscribe_session,
+            get_messages_since,
+            get_session_turns,
+            fork_session,
+            list_cli_sessions,
+            import_cli_session,
+            export_conversation,
+            import_conversation,
+            search_sessions,
+            get_log_level,
+            set_log_level,
+            create_log_bundle,
+            subscribe_logs,
+            unsubscribe_logs,
             abort_session,
             clear_session,
             check_claude_cli,
             check_claude_auth,
             get_claude_cli_status,
             quick_claude_check,
+            get_cli_parse_stats,
             check_git_status,
             git_checkout_branch,
             git_create_branch,
-            git_stash_changes
+            git_stash_changes,
+            list_checkpoints,
+            restore_checkpoint,
+            check_merge,
+            merge_branch,
+            list_mcp_servers,
+            save_mcp_server,
+            remove_mcp_server,
+            get_agent_mcp_servers,
+            set_agent_mcp_servers,
+            check_mcp_server,
+            start_control_server,
+            stop_control_server,
+            get_control_server_status,
+            list_agent_templates,
+            agent_from_template,
+            import_agent_template,
+            export_agent_template,
+            remove_agent_template,
+            open_project,
+            get_project,
+            list_projects,
+            list_recent_projects,
+            update_project,
+            remove_project,
+            save_project_agent,
+            remove_project_agent,
+            add_project_worktree
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
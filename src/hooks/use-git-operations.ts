@@ -7,6 +7,49 @@ export interface GitCheckoutOptions {
   force: boolean
 }
 
+/** Snapshot of a worktree taken before an agent turn */
+export interface Checkpoint {
+  id: string
+  commit: string
+  head?: string
+  sessionId?: string
+  messageSeq?: number
+  label: string
+  createdAt: string
+}
+
+export type MergeMethod = 'merge' | 'rebase'
+
+export interface MergeOptions {
+  method?: MergeMethod
+  message?: string
+  noFastForward?: boolean
+}
+
+/** A file two branches change in ways git can't combine; a missing version was deleted, binary or too large */
+export interface ConflictedFile {
+  path: string
+  messages: string[]
+  base?: string
+  target?: string
+  source?: string
+}
+
+export interface MergeReport {
+  source: string
+  target: string
+  sourceCommit: string
+  targetCommit: string
+  mergeBase?: string
+  commitsAhead: number
+  commitsBehind: number
+  conflicts: ConflictedFile[]
+  /** Set once the merge is done */
+  mergedCommit?: string
+  /** Session started to resolve the conflicts, when `resolutionAgentId` was given */
+  resolutionSessionId?: string
+}
+
 export function useGitOperations(directory: string | undefined) {
   const queryClient = useQueryClient()
 
@@ -66,9 +109,61 @@ export function useGitOperations(directory: string | undefined) {
     }
   })
 
+  const restoreCheckpoint = useMutation({
+    mutationFn: async (checkpointId: string) => {
+      if (!directory) throw new Error('No directory selected')
+
+      return await invoke<Checkpoint>('restore_checkpoint', {
+        directory,
+        checkpointId
+      })
+    },
+    onSuccess: () => {
+      toast.success('Restored checkpoint')
+      queryClient.invalidateQueries({ queryKey: ['git-status', directory] })
+    },
+    onError: (error: Error) => {
+      toast.error(`Failed to restore checkpoint: ${error.message}`)
+    }
+  })
+
+  const mergeBranch = useMutation({
+    mutationFn: async ({ source, target, options, resolutionAgentId }: {
+      source: string
+      target: string
+      options?: MergeOptions
+      resolutionAgentId?: string
+    }) => {
+      if (!directory) throw new Error('No directory selected')
+
+      return await invoke<MergeReport>('merge_branch', {
+        directory,
+        source,
+        target,
+        options,
+        resolutionAgentId
+      })
+    },
+    onSuccess: (report) => {
+      if (report.mergedCommit) {
+        toast.success(`Merged ${report.source} into ${report.target}`)
+      } else if (report.resolutionSessionId) {
+        toast.warning(`${report.conflicts.length} conflicts, started an agent to resolve them`)
+      } else {
+        toast.warning(`Merging ${report.source} into ${report.target} conflicts in ${report.conflicts.length} files`)
+      }
+      queryClient.invalidateQueries({ queryKey: ['git-status', directory] })
+    },
+    onError: (error: Error) => {
+      toast.error(`Failed to merge: ${error.message}`)
+    }
+  })
+
   return {
     checkoutBranch,
     createBranch,
-    stashChanges
+    stashChanges,
+    restoreCheckpoint,
+    mergeBranch
   }
 }
\ No newline at end of file
@@ -1,5 +1,7 @@
-import { useQuery } from '@tanstack/react-query'
+import { useEffect } from 'react'
+import { useQuery, useQueryClient } from '@tanstack/react-query'
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 
 export interface GitStatus {
   isRepo: boolean
@@ -9,7 +11,33 @@ export interface GitStatus {
   branches: string[]
 }
 
+/** Sent while agents run, when a watched worktree's status changes */
+export interface GitStatusChanged {
+  directory: string
+  projectId?: string
+  status: GitStatus
+}
+
+/** Files agents changed in a watched worktree, leaving out `.git` and ignored files */
+export interface FilesChanged {
+  directory: string
+  projectId?: string
+  paths: string[]
+}
+
 export function useGitStatus(directory: string | undefined) {
+  const queryClient = useQueryClient()
+
+  // Agent edits arrive as events; the slow poll only catches changes made outside the app
+  useEffect(() => {
+    const unlisten = listen<GitStatusChanged>('git-status-changed', () => {
+      queryClient.invalidateQueries({ queryKey: ['git-status'] })
+    })
+    return () => {
+      unlisten.then((fn) => fn())
+    }
+  }, [queryClient])
+
   return useQuery<GitStatus>({
     queryKey: ['git-status', directory],
     queryFn: async () => {
@@ -25,6 +53,6 @@ export function useGitStatus(directory: string | undefined) {
       return await invoke<GitStatus>('check_git_status', { directory })
     },
     enabled: !!directory,
-    refetchInterval: 5000, // Refetch every 5 seconds to catch external changes
+    refetchInterval: 30000,
   })
-}
\ No newline at end of file
+}
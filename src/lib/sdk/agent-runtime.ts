@@ -81,9 +81,12 @@ export class AgentRuntime {
       ...options,
       abortController: agent.abortController,
       cwd: agent.config.workingDirectory || options?.cwd,
-      customSystemPrompt: agent.config.systemPrompt || options?.customSystemPrompt,
+      appendSystemPrompt: agent.config.systemPrompt || options?.appendSystemPrompt,
       model: agent.config.model || options?.model,
       maxTurns: agent.config.maxTurns || options?.maxTurns,
+      allowedTools: agent.config.allowedTools || options?.allowedTools,
+      disallowedTools: agent.config.disallowedTools || options?.disallowedTools,
+      permissionMode: agent.config.permissionMode || options?.permissionMode,
     }
 
     // Add user message immediately
@@ -18,8 +18,30 @@ interface CreateSessionResponse {
   sessionId: string
 }
 
+type ErrorCode =
+  | 'CliNotFound'
+  | 'NotAuthenticated'
+  | 'CliRejectedArguments'
+  | 'RateLimited'
+  | 'CliFailed'
+  | 'SessionNotFound'
+  | 'NotFound'
+  | 'InvalidInput'
+  | 'Unauthorized'
+  | 'NotAGitRepository'
+  | 'GitConflict'
+  | 'DirtyWorktree'
+  | 'GitFailed'
+  | 'Timeout'
+  | 'Network'
+  | 'Io'
+  | 'Internal'
+
 interface ErrorResponse {
   error: string
+  code: ErrorCode
+  details?: unknown
+  retryable: boolean
 }
 
 interface ClaudeCliStatus {
@@ -100,7 +122,8 @@ export class ClaudeSDKTauri {
     // Set up event listeners
     const unsubscribeMessage = await listen<Message>('claude-message', (event) => {
       const message = event.payload
-      if (message.sessionId === sessionId) {
+      // The prompt is already shown by agent-runtime; the backend echoes it only for its seq
+      if (message.sessionId === sessionId && message.role !== 'user') {
         session.messages.push(message)
         messageQueue.push(message)
         
@@ -1,10 +1,15 @@
 export type AgentType = 'task' | 'explorer' | 'builder' | 'review'
 export type AgentStatus = 'idle' | 'running' | 'completed' | 'error'
 
+export type PermissionMode = 'default' | 'acceptEdits' | 'bypassPermissions' | 'plan'
+
 export interface AgentConfig {
   id: string
   name: string
   type: AgentType
+  /** Template the agent was created from */
+  template?: string
+  /** Added to the CLI's own system prompt */
   systemPrompt?: string
   workingDirectory?: string
   branch?: string
@@ -12,9 +17,27 @@ export interface AgentConfig {
   maxTokens?: number
   temperature?: number
   maxTurns?: number
+  allowedTools?: string[]
+  disallowedTools?: string[]
+  permissionMode?: PermissionMode
   mcpExtensions?: string[]
 }
 
+/** Defaults for new agents, from the built-in library or the user's templates directory */
+export interface AgentTemplate {
+  id: string
+  name: string
+  type: AgentType
+  description?: string
+  systemPrompt?: string
+  model?: string
+  maxTurns?: number
+  allowedTools?: string[]
+  disallowedTools?: string[]
+  permissionMode?: PermissionMode
+  source: 'builtin' | 'user'
+}
+
 export interface QueryOptions {
   abortController?: AbortController
   allowedTools?: string[]
@@ -26,7 +49,7 @@ export interface QueryOptions {
   cwd?: string
   customSystemPrompt?: string
   appendSystemPrompt?: string
-  permissionMode?: 'default' | 'acceptEdits' | 'bypassPermissions' | 'plan'
+  permissionMode?: PermissionMode
   continue?: boolean
   resume?: string
 }
@@ -37,6 +60,8 @@ export interface Message {
   toolCalls?: ToolCall[]
   timestamp: Date
   sessionId?: string
+  /** Position in the session's history, for spotting gaps and duplicates */
+  seq?: number
 }
 
 export interface ToolCall {
@@ -49,6 +74,47 @@ export interface ToolCall {
   result?: string
 }
 
+/** Files a turn touched and the commands it ran */
+export interface TurnActivity {
+  filesRead: string[]
+  /** Created or overwritten with `Write` */
+  filesWritten: string[]
+  /** Changed in place with `Edit`, `MultiEdit` or `NotebookEdit` */
+  filesEdited: string[]
+  commands: BashCommand[]
+}
+
+export interface BashCommand {
+  command: string
+  description?: string
+  failed: boolean
+}
+
+/** Everything the CLI reported about a finished query, as returned by `get_session_turns` */
+export interface TurnSummary {
+  sessionId: string
+  cliSessionId?: string
+  subtype: string
+  isError: boolean
+  result?: string
+  error?: string
+  numTurns?: number
+  durationMs?: number
+  durationApiMs?: number
+  totalCostUsd?: number
+  usage?: {
+    inputTokens: number
+    outputTokens: number
+    cacheCreationInputTokens?: number
+    cacheReadInputTokens?: number
+  }
+  attempts: number
+  activity: TurnActivity
+  /** Worktree snapshot from before the turn, for `restore_checkpoint` */
+  checkpointId?: string
+  completedAt: string
+}
+
 export interface ConversationExport {
   agentId: string
   messages: Message[]
@@ -0,0 +1,37 @@
+import type { AgentConfig, PermissionMode } from './agent'
+
+/** A repository the user works in, with the agents and worktrees that belong to it */
+export interface Project {
+  id: string
+  name: string
+  /** Canonical path of the project's main directory */
+  rootPath: string
+  /** Branch agent worktrees start from */
+  defaultBranch?: string
+  agents: AgentConfig[]
+  worktrees: ProjectWorktree[]
+  settings: ProjectSettings
+  createdAt: string
+  lastOpenedAt: string
+}
+
+export interface ProjectWorktree {
+  path: string
+  branch: string
+  agentId?: string
+  createdAt: string
+}
+
+/** Defaults for queries run in the project when the agent and prompt leave them unset */
+export interface ProjectSettings {
+  model?: string
+  permissionMode?: PermissionMode
+  /** Where new worktrees go; defaults to `<root>-worktrees` next to the root */
+  worktreeDir?: string
+}
+
+export interface ProjectUpdate {
+  name?: string
+  defaultBranch?: string
+  settings?: ProjectSettings
+}